@@ -0,0 +1,173 @@
+// Resolves the cgroup directories of the current process, shared by the cpu and memory
+// limit readers. Paths are resolved against a configurable root like the sysfs helpers.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CgroupVersion {
+    V1,
+    V2,
+}
+
+/// A line of /proc/self/cgroup: `hierarchy-id:controller-list:path`
+struct CgroupEntry {
+    controllers: Vec<String>,
+    path: String,
+}
+
+/// cgroup membership of the current process
+pub(crate) struct Cgroup {
+    root: PathBuf,
+    entries: Vec<CgroupEntry>,
+}
+
+impl Cgroup {
+    /// Reads `<root>/proc/self/cgroup`, `None` when the process is not in any cgroup
+    pub(crate) fn current<P: AsRef<Path>>(root: P) -> Option<Self> {
+        let content = std::fs::read_to_string(root.as_ref().join("proc/self/cgroup")).ok()?;
+
+        let entries: Vec<CgroupEntry> = content
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, ':');
+                let _hierarchy = parts.next()?;
+                let controllers = parts
+                    .next()?
+                    .split(',')
+                    .filter(|c| !c.is_empty())
+                    .map(|c| c.to_string())
+                    .collect();
+                let path = parts.next()?.to_string();
+                Some(CgroupEntry { controllers, path })
+            })
+            .collect();
+
+        if entries.is_empty() {
+            return None;
+        }
+
+        Some(Cgroup {
+            root: root.as_ref().to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Directories that hold the files of `controller` for this process, starting with the
+    /// process's own cgroup followed by its ancestors up to the mount point, since limits
+    /// set on a parent apply to all of its children.
+    ///
+    /// A v1 hierarchy is preferred when the controller is bound to one (hybrid setups),
+    /// otherwise the unified v2 hierarchy is used.
+    pub(crate) fn controller_dirs(
+        &self,
+        controller: &str,
+    ) -> Option<(CgroupVersion, Vec<PathBuf>)> {
+        let base = self.root.join("sys/fs/cgroup");
+
+        if let Some(entry) = self
+            .entries
+            .iter()
+            .find(|e| e.controllers.iter().any(|c| c == controller))
+        {
+            // v1 controllers are mounted either by their joined name (`cpu,cpuacct`) or
+            // one of the names, distributions usually symlink both
+            let mount = std::iter::once(entry.controllers.join(","))
+                .chain(entry.controllers.iter().cloned())
+                .map(|name| base.join(name))
+                .find(|dir| dir.is_dir())?;
+
+            return Some((CgroupVersion::V1, Self::ancestors(&mount, &entry.path)));
+        }
+
+        let unified = self.entries.iter().find(|e| e.controllers.is_empty())?;
+        if !base.join("cgroup.controllers").is_file() {
+            return None;
+        }
+
+        Some((CgroupVersion::V2, Self::ancestors(&base, &unified.path)))
+    }
+
+    fn ancestors(mount: &Path, path: &str) -> Vec<PathBuf> {
+        let mut dir = mount.join(path.trim_start_matches('/'));
+        // inside a container without a cgroup namespace the path is the host's view,
+        // while the container only sees its own cgroup mounted at the root
+        if !dir.is_dir() {
+            dir = mount.to_path_buf();
+        }
+
+        let mut dirs = vec![dir.clone()];
+        while dir != mount {
+            match dir.parent() {
+                Some(parent) => {
+                    dir = parent.to_path_buf();
+                    dirs.push(dir.clone());
+                }
+                None => break,
+            }
+        }
+        dirs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    #[test]
+    fn lists_ancestors_up_to_the_mount() {
+        let root = FakeRoot::new("cgroup-v2");
+        root.write("proc/self/cgroup", "0::/system.slice/app.service\n")
+            .write("sys/fs/cgroup/cgroup.controllers", "cpu memory\n")
+            .mkdir("sys/fs/cgroup/system.slice/app.service");
+
+        let cgroup = Cgroup::current(root.path()).unwrap();
+        let (version, dirs) = cgroup.controller_dirs("memory").unwrap();
+        let base = root.path().join("sys/fs/cgroup");
+        assert_eq!(version, CgroupVersion::V2);
+        assert_eq!(
+            dirs,
+            vec![
+                base.join("system.slice/app.service"),
+                base.join("system.slice"),
+                base.clone(),
+            ]
+        );
+    }
+
+    #[test]
+    fn prefers_v1_hierarchies() {
+        let root = FakeRoot::new("cgroup-hybrid");
+        root.write(
+            "proc/self/cgroup",
+            "7:memory:/user.slice\n1:name=systemd:/user.slice\n0::/user.slice\n",
+        )
+        .write("sys/fs/cgroup/cgroup.controllers", "\n")
+        .mkdir("sys/fs/cgroup/memory/user.slice");
+
+        let cgroup = Cgroup::current(root.path()).unwrap();
+        let (version, dirs) = cgroup.controller_dirs("memory").unwrap();
+        assert_eq!(version, CgroupVersion::V1);
+        assert_eq!(dirs[0], root.path().join("sys/fs/cgroup/memory/user.slice"));
+        // the cpu controller is on neither hierarchy here
+        assert_eq!(cgroup.controller_dirs("cpu").unwrap().0, CgroupVersion::V2);
+    }
+
+    #[test]
+    fn falls_back_to_the_mount_in_containers() {
+        // without a cgroup namespace the path is the host's, the container sees its own
+        // cgroup at the mount point
+        let root = FakeRoot::new("cgroup-container");
+        root.write("proc/self/cgroup", "0::/kubepods/pod1/abc\n")
+            .write("sys/fs/cgroup/cgroup.controllers", "cpu memory\n");
+
+        let (_, dirs) = Cgroup::current(root.path())
+            .unwrap()
+            .controller_dirs("cpu")
+            .unwrap();
+        assert_eq!(dirs, vec![root.path().join("sys/fs/cgroup")]);
+        assert!(Cgroup::current(root.path().join("missing")).is_none());
+    }
+}
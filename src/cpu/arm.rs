@@ -0,0 +1,194 @@
+use std::path::Path;
+
+use super::CPUVendor;
+use crate::sysfs;
+
+impl CPUVendor {
+    /// Maps the MIDR implementer code (`CPU implementer` in /proc/cpuinfo) to a vendor
+    pub fn from_arm_implementer(implementer: u32) -> Self {
+        match implementer {
+            0x41 => CPUVendor::Arm,
+            0x42 => CPUVendor::Broadcom,
+            0x43 => CPUVendor::Cavium,
+            0x46 => CPUVendor::Fujitsu,
+            0x48 => CPUVendor::HiSilicon,
+            0x4e => CPUVendor::Nvidia,
+            0x50 => CPUVendor::AppliedMicro,
+            0x51 => CPUVendor::Qualcomm,
+            0x53 => CPUVendor::Samsung,
+            0x56 => CPUVendor::Marvell,
+            0x61 => CPUVendor::Apple,
+            0x69 => CPUVendor::Intel,
+            0x6d => CPUVendor::Microsoft,
+            0xc0 => CPUVendor::Ampere,
+            _ => CPUVendor::Other,
+        }
+    }
+
+    /// Vendors shipping licensed Arm Ltd. cores (Graviton, Grace, Altra) report the Arm
+    /// implementer, the actual vendor comes from the SMCCC SoC id or the DMI vendor strings
+    pub(crate) fn arm_licensee<P: AsRef<Path>>(root: P) -> Option<Self> {
+        let root = root.as_ref();
+
+        // format is `jep106:<bank><identity>:<soc id>`, the bank counts continuation codes
+        if let Some(soc_id) = sysfs::read_string(root.join("sys/devices/soc0/soc_id")) {
+            let manufacturer = soc_id
+                .strip_prefix("jep106:")
+                .and_then(|id| id.split(':').next());
+            match manufacturer {
+                Some("036b") => return Some(CPUVendor::Nvidia),
+                Some("0a16") => return Some(CPUVendor::Ampere),
+                _ => {}
+            }
+        }
+
+        let dmi = root.join("sys/devices/virtual/dmi/id");
+        let sys_vendor = sysfs::read_string(dmi.join("sys_vendor")).unwrap_or_default();
+        let board_vendor = sysfs::read_string(dmi.join("board_vendor")).unwrap_or_default();
+
+        if sys_vendor == "Amazon EC2" {
+            Some(CPUVendor::Amazon)
+        } else if sys_vendor.contains("Ampere") || board_vendor.contains("Ampere") {
+            // reference platforms such as the Mt. Jade / Mt. Snow boards
+            Some(CPUVendor::Ampere)
+        } else {
+            None
+        }
+    }
+}
+
+/// Core name for a MIDR implementer / part number pair
+pub(crate) fn arm_microarchitecture(implementer: u32, part: u32) -> Option<&'static str> {
+    let name = match (implementer, part) {
+        // Arm Ltd.
+        (0x41, 0xd02) => "Cortex-A34",
+        (0x41, 0xd03) => "Cortex-A53",
+        (0x41, 0xd04) => "Cortex-A35",
+        (0x41, 0xd05) => "Cortex-A55",
+        (0x41, 0xd06) => "Cortex-A65",
+        (0x41, 0xd07) => "Cortex-A57",
+        (0x41, 0xd08) => "Cortex-A72",
+        (0x41, 0xd09) => "Cortex-A73",
+        (0x41, 0xd0a) => "Cortex-A75",
+        (0x41, 0xd0b) => "Cortex-A76",
+        (0x41, 0xd0c) => "Neoverse-N1",
+        (0x41, 0xd0d) => "Cortex-A77",
+        (0x41, 0xd0e) => "Cortex-A76AE",
+        (0x41, 0xd40) => "Neoverse-V1",
+        (0x41, 0xd41) => "Cortex-A78",
+        (0x41, 0xd42) => "Cortex-A78AE",
+        (0x41, 0xd44) => "Cortex-X1",
+        (0x41, 0xd46) => "Cortex-A510",
+        (0x41, 0xd47) => "Cortex-A710",
+        (0x41, 0xd48) => "Cortex-X2",
+        (0x41, 0xd49) => "Neoverse-N2",
+        (0x41, 0xd4a) => "Neoverse-E1",
+        (0x41, 0xd4b) => "Cortex-A78C",
+        (0x41, 0xd4c) => "Cortex-X1C",
+        (0x41, 0xd4d) => "Cortex-A715",
+        (0x41, 0xd4e) => "Cortex-X3",
+        (0x41, 0xd4f) => "Neoverse-V2",
+        (0x41, 0xd80) => "Cortex-A520",
+        (0x41, 0xd81) => "Cortex-A720",
+        (0x41, 0xd82) => "Cortex-X4",
+        (0x41, 0xd84) => "Neoverse-V3",
+        (0x41, 0xd85) => "Cortex-X925",
+        (0x41, 0xd87) => "Cortex-A725",
+        (0x41, 0xd8e) => "Neoverse-N3",
+        // Broadcom
+        (0x42, 0x100) => "Brahma-B53",
+        (0x42, 0x516) => "ThunderX2",
+        // Cavium
+        (0x43, 0x0a1) => "ThunderX",
+        (0x43, 0x0af) => "ThunderX2",
+        // Fujitsu
+        (0x46, 0x001) => "A64FX",
+        // HiSilicon
+        (0x48, 0xd01) => "TaiShan-v110",
+        (0x48, 0xd02) => "TaiShan-v120",
+        // Nvidia
+        (0x4e, 0x000) => "Denver",
+        (0x4e, 0x003) => "Denver 2",
+        (0x4e, 0x004) => "Carmel",
+        (0x4e, 0x010) => "Olympus",
+        // Applied Micro
+        (0x50, 0x000) => "X-Gene",
+        // Qualcomm
+        (0x51, 0x001) => "Oryon",
+        (0x51, 0x800) => "Kryo 2XX Gold",
+        (0x51, 0x801) => "Kryo 2XX Silver",
+        (0x51, 0x802) => "Kryo 3XX Gold",
+        (0x51, 0x803) => "Kryo 3XX Silver",
+        (0x51, 0x804) => "Kryo 4XX Gold",
+        (0x51, 0x805) => "Kryo 4XX Silver",
+        (0x51, 0xc00) => "Falkor",
+        (0x51, 0xc01) => "Saphira",
+        // Apple
+        (0x61, 0x022) => "Icestorm",
+        (0x61, 0x023) => "Firestorm",
+        (0x61, 0x024) => "Icestorm Pro",
+        (0x61, 0x025) => "Firestorm Pro",
+        (0x61, 0x028) => "Icestorm Max",
+        (0x61, 0x029) => "Firestorm Max",
+        (0x61, 0x032) => "Blizzard",
+        (0x61, 0x033) => "Avalanche",
+        // Microsoft
+        (0x6d, 0xd49) => "Azure Cobalt 100",
+        // Ampere
+        (0xc0, 0xac3) => "Ampere-1",
+        (0xc0, 0xac4) => "Ampere-1a",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    #[test]
+    fn finds_licensee_from_soc_id() {
+        let root = FakeRoot::new("arm-soc-id");
+        root.write("sys/devices/soc0/soc_id", "jep106:036b:0241\n");
+        assert_eq!(
+            CPUVendor::arm_licensee(root.path()),
+            Some(CPUVendor::Nvidia)
+        );
+
+        root.write("sys/devices/soc0/soc_id", "jep106:0a16:0001\n");
+        assert_eq!(
+            CPUVendor::arm_licensee(root.path()),
+            Some(CPUVendor::Ampere)
+        );
+    }
+
+    #[test]
+    fn finds_licensee_from_dmi() {
+        let root = FakeRoot::new("arm-dmi");
+        root.write("sys/devices/soc0/soc_id", "jep106:043b:0001\n")
+            .write("sys/devices/virtual/dmi/id/sys_vendor", "Amazon EC2\n");
+        assert_eq!(
+            CPUVendor::arm_licensee(root.path()),
+            Some(CPUVendor::Amazon)
+        );
+
+        root.write("sys/devices/virtual/dmi/id/sys_vendor", "GIGABYTE\n")
+            .write(
+                "sys/devices/virtual/dmi/id/board_vendor",
+                "Ampere Computing\n",
+            );
+        assert_eq!(
+            CPUVendor::arm_licensee(root.path()),
+            Some(CPUVendor::Ampere)
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_licensees() {
+        let root = FakeRoot::new("arm-unknown");
+        root.write("sys/devices/virtual/dmi/id/sys_vendor", "Raspberry Pi\n");
+        assert_eq!(CPUVendor::arm_licensee(root.path()), None);
+    }
+}
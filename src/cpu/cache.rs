@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::cpu::CoreType;
+use crate::sysfs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheType {
+    Data,
+    Instruction,
+    Unified,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuCache {
+    pub level: u32,
+    pub cache_type: CacheType,
+    /// in bytes
+    pub size: u64,
+    /// in bytes
+    pub line_size: Option<u32>,
+    pub ways_of_associativity: Option<u32>,
+    /// Logical cpus sharing this cache instance, empty when unknown (e.g. soc database entries)
+    #[serde(default)]
+    pub shared_cpus: Vec<u32>,
+    /// Core type owning this cache on hybrid cpus, `None` when it is shared by both or unknown
+    #[serde(default)]
+    pub core_type: Option<CoreType>,
+}
+
+impl CpuCache {
+    /// Reads every distinct cache instance from `<root>/sys/devices/system/cpu/cpuN/cache/indexM`.
+    ///
+    /// A cache shared by several cpus is listed once, ordered by level and type.
+    pub fn read_all<P: AsRef<Path>>(root: P) -> Vec<CpuCache> {
+        let root = root.as_ref();
+        let mut caches: Vec<CpuCache> = Vec::new();
+
+        for cpu in sysfs::list_cpus(root) {
+            let dir = root.join(format!("sys/devices/system/cpu/cpu{}/cache", cpu));
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for entry in entries.filter_map(|e| e.ok()) {
+                if !entry.file_name().to_string_lossy().starts_with("index") {
+                    continue;
+                }
+
+                if let Some(cache) = Self::read(&entry.path(), cpu) {
+                    let duplicate = caches.iter().any(|c| {
+                        c.level == cache.level
+                            && c.cache_type == cache.cache_type
+                            && c.shared_cpus == cache.shared_cpus
+                    });
+                    if !duplicate {
+                        caches.push(cache);
+                    }
+                }
+            }
+        }
+
+        caches.sort_by_key(|c| (c.level, c.cache_type as u8, c.shared_cpus.first().copied()));
+        caches
+    }
+
+    fn read(dir: &Path, cpu: u32) -> Option<CpuCache> {
+        let level = sysfs::read_u64(dir.join("level"))? as u32;
+        let cache_type = match sysfs::read_string(dir.join("type"))?.as_str() {
+            "Data" => CacheType::Data,
+            "Instruction" => CacheType::Instruction,
+            "Unified" => CacheType::Unified,
+            _ => return None,
+        };
+        let size = parse_size(&sysfs::read_string(dir.join("size"))?)?;
+
+        let shared_cpus = sysfs::read_string(dir.join("shared_cpu_list"))
+            .map(|list| sysfs::parse_cpu_list(&list))
+            .filter(|cpus| !cpus.is_empty())
+            .unwrap_or_else(|| vec![cpu]);
+
+        Some(CpuCache {
+            level,
+            cache_type,
+            size,
+            line_size: sysfs::read_u64(dir.join("coherency_line_size")).map(|v| v as u32),
+            ways_of_associativity: sysfs::read_u64(dir.join("ways_of_associativity"))
+                .map(|v| v as u32),
+            shared_cpus,
+            core_type: None,
+        })
+    }
+}
+
+/// Parses sysfs cache sizes such as `48K` or `30M` into bytes
+fn parse_size(size: &str) -> Option<u64> {
+    let (number, multiplier) = match size.chars().last()? {
+        'K' => (&size[..size.len() - 1], 1024),
+        'M' => (&size[..size.len() - 1], 1024 * 1024),
+        'G' => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+
+    number.trim().parse::<u64>().ok().map(|n| n * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    fn add_cache(root: &FakeRoot, cpu: u32, index: u32, cache: (&str, &str, &str, &str)) {
+        let dir = format!("sys/devices/system/cpu/cpu{}/cache/index{}", cpu, index);
+        let (level, cache_type, size, shared_cpu_list) = cache;
+        root.write(&format!("{}/level", dir), level)
+            .write(&format!("{}/type", dir), cache_type)
+            .write(&format!("{}/size", dir), size)
+            .write(&format!("{}/shared_cpu_list", dir), shared_cpu_list)
+            .write(&format!("{}/coherency_line_size", dir), "64\n");
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("48K"), Some(48 * 1024));
+        assert_eq!(parse_size("30M"), Some(30 * 1024 * 1024));
+        assert_eq!(parse_size("1G"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("K"), None);
+        assert_eq!(parse_size(""), None);
+    }
+
+    #[test]
+    fn lists_shared_caches_once() {
+        let root = FakeRoot::new("cpu-caches");
+        for cpu in 0..2 {
+            let own = format!("{}\n", cpu);
+            add_cache(&root, cpu, 0, ("1\n", "Data\n", "48K\n", &own));
+            add_cache(&root, cpu, 1, ("1\n", "Instruction\n", "32K\n", &own));
+            add_cache(&root, cpu, 2, ("2\n", "Unified\n", "2048K\n", "0-1\n"));
+            add_cache(&root, cpu, 3, ("3\n", "Unified\n", "30M\n", "0-1\n"));
+        }
+
+        let caches: Vec<(u32, CacheType, u64, Vec<u32>)> = CpuCache::read_all(root.path())
+            .into_iter()
+            .map(|c| (c.level, c.cache_type, c.size, c.shared_cpus))
+            .collect();
+        assert_eq!(
+            caches,
+            vec![
+                (1, CacheType::Data, 48 * 1024, vec![0]),
+                (1, CacheType::Data, 48 * 1024, vec![1]),
+                (1, CacheType::Instruction, 32 * 1024, vec![0]),
+                (1, CacheType::Instruction, 32 * 1024, vec![1]),
+                (2, CacheType::Unified, 2048 * 1024, vec![0, 1]),
+                (3, CacheType::Unified, 30 * 1024 * 1024, vec![0, 1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_unknown_cache_types() {
+        let root = FakeRoot::new("cpu-caches-unknown");
+        add_cache(&root, 0, 0, ("1\n", "Data\n", "64K\n", "\n"));
+        add_cache(&root, 0, 1, ("4\n", "Trace\n", "12K\n", "0\n"));
+
+        let caches = CpuCache::read_all(root.path());
+        assert_eq!(caches.len(), 1);
+        // an empty sharing list means the cache belongs to the cpu it was found under
+        assert_eq!(caches[0].shared_cpus, vec![0]);
+        assert_eq!(caches[0].line_size, Some(64));
+    }
+}
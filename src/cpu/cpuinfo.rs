@@ -0,0 +1,305 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::arm::arm_microarchitecture;
+use super::CPUVendor;
+
+/// A single `processor` block from /proc/cpuinfo
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessorInfo {
+    pub processor: u32,
+    pub vendor_id: Option<String>,
+    pub model_name: Option<String>,
+    pub family: Option<u32>,
+    pub model: Option<u32>,
+    pub stepping: Option<u32>,
+    pub microcode: Option<String>,
+    /// x86 `flags` or ARM `Features`
+    pub flags: Vec<String>,
+    /// in MHz
+    pub frequency: Option<f32>,
+    pub physical_id: Option<u32>,
+    pub core_id: Option<u32>,
+
+    // ===== ARM =====
+    pub implementer: Option<u32>,
+    pub variant: Option<u32>,
+    pub part: Option<u32>,
+    pub revision: Option<u32>,
+
+    // ===== RISC-V =====
+    pub hart: Option<u32>,
+    pub isa: Option<String>,
+    pub mmu: Option<String>,
+    pub uarch: Option<String>,
+}
+
+/// Parsed contents of /proc/cpuinfo
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcCpuInfo {
+    pub processors: Vec<ProcessorInfo>,
+    /// `Hardware` line printed by some ARM kernels outside of the processor blocks
+    pub hardware: Option<String>,
+}
+
+impl ProcCpuInfo {
+    /// Reads and parses `<root>/proc/cpuinfo`, pass `/` for the running system
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(root.as_ref().join("proc/cpuinfo"))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut result = ProcCpuInfo::default();
+
+        // blocks are separated by empty lines, the ones without a `processor` key
+        // (e.g. `Hardware`, `Revision` on ARM) hold system wide values
+        for block in content.split("\n\n") {
+            let fields = Self::parse_block(block);
+            if fields.is_empty() {
+                continue;
+            }
+
+            if let Some(hardware) = fields.get("Hardware") {
+                result.hardware = Some(hardware.to_string());
+            }
+
+            let processor = match fields.get("processor").and_then(|p| p.parse::<u32>().ok()) {
+                Some(processor) => processor,
+                None => continue,
+            };
+
+            let text = |key: &str| {
+                fields
+                    .get(key)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| v.to_string())
+            };
+            let number = |key: &str| fields.get(key).and_then(|v| parse_number(v));
+
+            result.processors.push(ProcessorInfo {
+                processor,
+                vendor_id: text("vendor_id"),
+                model_name: text("model name"),
+                family: number("cpu family"),
+                model: number("model"),
+                stepping: number("stepping"),
+                microcode: text("microcode"),
+                flags: fields
+                    .get("flags")
+                    .or_else(|| fields.get("Features"))
+                    .map(|f| f.split_whitespace().map(|s| s.to_string()).collect())
+                    .unwrap_or_default(),
+                frequency: fields.get("cpu MHz").and_then(|v| v.parse::<f32>().ok()),
+                physical_id: number("physical id"),
+                core_id: number("core id"),
+                implementer: number("CPU implementer"),
+                variant: number("CPU variant"),
+                part: number("CPU part"),
+                revision: number("CPU revision"),
+                hart: number("hart"),
+                isa: text("isa"),
+                mmu: text("mmu"),
+                uarch: text("uarch"),
+            });
+        }
+
+        result
+    }
+
+    fn parse_block(block: &str) -> HashMap<&str, &str> {
+        block
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect()
+    }
+
+    /// Human readable CPU name, falls back to `Hardware`, the ARM core name and RISC-V
+    /// `uarch` when the kernel does not print a `model name`
+    pub fn model_name(&self) -> Option<String> {
+        let first = self.processors.first();
+
+        first
+            .and_then(|p| p.model_name.clone())
+            .or_else(|| self.hardware.clone())
+            .or_else(|| self.microarchitecture())
+            .or_else(|| first.and_then(|p| p.uarch.clone()))
+    }
+
+    /// x86 `vendor_id` or the ARM `CPU implementer`
+    pub fn vendor(&self) -> CPUVendor {
+        let first = match self.processors.first() {
+            Some(first) => first,
+            None => return CPUVendor::Other,
+        };
+
+        match (&first.vendor_id, first.implementer) {
+            (Some(vendor_id), _) => CPUVendor::from_vendor_id(vendor_id),
+            (None, Some(implementer)) => CPUVendor::from_arm_implementer(implementer),
+            _ => CPUVendor::Other,
+        }
+    }
+
+    /// ARM core names of all core types in cpu order, e.g. `Cortex-A55 / Cortex-A78`
+    pub fn microarchitecture(&self) -> Option<String> {
+        let mut names: Vec<&str> = Vec::new();
+        for processor in &self.processors {
+            if let (Some(implementer), Some(part)) = (processor.implementer, processor.part) {
+                if let Some(name) = arm_microarchitecture(implementer, part) {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+
+        if names.is_empty() {
+            None
+        } else {
+            Some(names.join(" / "))
+        }
+    }
+
+    pub fn family(&self) -> Option<u32> {
+        self.processors.first().and_then(|p| p.family)
+    }
+
+    pub fn model(&self) -> Option<u32> {
+        self.processors.first().and_then(|p| p.model)
+    }
+
+    pub fn stepping(&self) -> Option<u32> {
+        self.processors.first().and_then(|p| p.stepping)
+    }
+
+    pub fn microcode(&self) -> Option<String> {
+        self.processors.first().and_then(|p| p.microcode.clone())
+    }
+
+    pub fn flags(&self) -> Vec<String> {
+        self.processors
+            .first()
+            .map(|p| p.flags.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Parses decimal and `0x` prefixed hexadecimal values
+fn parse_number(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse::<u32>().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, FakeRoot};
+
+    fn parse_fixture(name: &str) -> ProcCpuInfo {
+        ProcCpuInfo::parse(&fixture(&format!("cpuinfo/{}", name)))
+    }
+
+    #[test]
+    fn parses_x86() {
+        let cpuinfo = parse_fixture("x86_64.txt");
+
+        assert_eq!(cpuinfo.processors.len(), 4);
+        assert_eq!(cpuinfo.vendor(), CPUVendor::AMD);
+        assert_eq!(
+            cpuinfo.model_name().as_deref(),
+            Some("AMD EPYC 9654 96-Core Processor")
+        );
+        assert_eq!(cpuinfo.family(), Some(25));
+        assert_eq!(cpuinfo.model(), Some(17));
+        assert_eq!(cpuinfo.stepping(), Some(1));
+        assert_eq!(cpuinfo.microcode().as_deref(), Some("0xa101148"));
+        assert!(cpuinfo.flags().iter().any(|f| f == "avx512f"));
+
+        let first = &cpuinfo.processors[0];
+        assert_eq!(first.frequency, Some(3705.427));
+        assert_eq!(first.physical_id, Some(0));
+        assert_eq!(cpuinfo.processors[3].core_id, Some(1));
+        assert_eq!(first.implementer, None);
+        assert_eq!(cpuinfo.microarchitecture(), None);
+    }
+
+    #[test]
+    fn parses_arm() {
+        let cpuinfo = parse_fixture("aarch64.txt");
+
+        assert_eq!(cpuinfo.processors.len(), 4);
+        assert_eq!(cpuinfo.vendor(), CPUVendor::Arm);
+        // arm64 kernels print no model name, the core name stands in for it
+        assert_eq!(cpuinfo.model_name().as_deref(), Some("Neoverse-V1"));
+        assert_eq!(cpuinfo.family(), None);
+
+        let first = &cpuinfo.processors[0];
+        assert_eq!(first.implementer, Some(0x41));
+        assert_eq!(first.variant, Some(0x1));
+        assert_eq!(first.part, Some(0xd40));
+        assert_eq!(first.revision, Some(1));
+        assert!(first.flags.iter().any(|f| f == "sve"));
+    }
+
+    #[test]
+    fn parses_hybrid_x86() {
+        let cpuinfo = parse_fixture("alderlake.txt");
+
+        assert_eq!(cpuinfo.processors.len(), 8);
+        assert_eq!(cpuinfo.vendor(), CPUVendor::Intel);
+        assert_eq!(cpuinfo.model(), Some(154));
+
+        // P-cores list two threads per core id, E-cores one
+        let mut cores: Vec<u32> = cpuinfo
+            .processors
+            .iter()
+            .filter_map(|p| p.core_id)
+            .collect();
+        cores.dedup();
+        assert_eq!(cores, vec![0, 4, 8, 9, 10, 11]);
+        assert_eq!(cpuinfo.processors[0].frequency, Some(4700.0));
+        assert_eq!(cpuinfo.processors[7].frequency, Some(3500.0));
+    }
+
+    #[test]
+    fn parses_hybrid_arm() {
+        let cpuinfo = parse_fixture("rk3588.txt");
+
+        assert_eq!(cpuinfo.processors.len(), 8);
+        assert_eq!(
+            cpuinfo.microarchitecture().as_deref(),
+            Some("Cortex-A55 / Cortex-A76")
+        );
+        assert_eq!(
+            cpuinfo.model_name().as_deref(),
+            Some("Cortex-A55 / Cortex-A76")
+        );
+        assert_eq!(cpuinfo.processors[4].part, Some(0xd0b));
+    }
+
+    #[test]
+    fn prefers_the_hardware_line() {
+        let cpuinfo = ProcCpuInfo::parse(
+            "processor\t: 0\nCPU implementer\t: 0x41\nCPU part\t: 0xd03\n\n\
+             Hardware\t: BCM2835\nRevision\t: a02082\n",
+        );
+
+        assert_eq!(cpuinfo.processors.len(), 1);
+        assert_eq!(cpuinfo.hardware.as_deref(), Some("BCM2835"));
+        assert_eq!(cpuinfo.model_name().as_deref(), Some("BCM2835"));
+    }
+
+    #[test]
+    fn reads_from_root() {
+        let root = FakeRoot::new("cpuinfo-root");
+        root.write("proc/cpuinfo", &fixture("cpuinfo/x86_64.txt"));
+
+        let cpuinfo = ProcCpuInfo::from_root(root.path()).unwrap();
+        assert_eq!(cpuinfo.processors.len(), 4);
+        assert!(ProcCpuInfo::from_root(root.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::sysfs;
+
+/// Clock data of a logical cpu from cpufreq sysfs, all frequencies are in MHz
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuFrequency {
+    pub cpu: u32,
+    pub current: Option<u32>,
+    /// Hardware limits
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+    /// Guaranteed (non turbo) frequency, only exposed by some drivers such as intel_pstate
+    pub base: Option<u32>,
+    /// Limits currently applied by the governor
+    pub scaling_min: Option<u32>,
+    pub scaling_max: Option<u32>,
+    pub driver: Option<String>,
+    pub governor: Option<String>,
+    pub energy_performance_preference: Option<String>,
+    /// Whether turbo / boost frequencies are allowed, `None` if the driver does not tell
+    pub boost: Option<bool>,
+}
+
+impl CpuFrequency {
+    /// Reads the frequency data of every cpu under `<root>/sys/devices/system/cpu`,
+    /// cpus without a cpufreq directory (e.g. most VMs) are skipped
+    pub fn read_all<P: AsRef<Path>>(root: P) -> Vec<CpuFrequency> {
+        let root = root.as_ref();
+        let boost = Self::global_boost(root);
+
+        sysfs::list_cpus(root)
+            .into_iter()
+            .filter_map(|cpu| Self::read(root, cpu, boost))
+            .collect()
+    }
+
+    fn read(root: &Path, cpu: u32, global_boost: Option<bool>) -> Option<CpuFrequency> {
+        let dir = root.join(format!("sys/devices/system/cpu/cpu{}/cpufreq", cpu));
+        if !dir.is_dir() {
+            return None;
+        }
+
+        let mhz = |file: &str| sysfs::read_u64(dir.join(file)).map(|khz| (khz / 1000) as u32);
+
+        Some(CpuFrequency {
+            cpu,
+            current: mhz("scaling_cur_freq").or_else(|| mhz("cpuinfo_cur_freq")),
+            min: mhz("cpuinfo_min_freq"),
+            max: mhz("cpuinfo_max_freq"),
+            base: mhz("base_frequency"),
+            scaling_min: mhz("scaling_min_freq"),
+            scaling_max: mhz("scaling_max_freq"),
+            driver: sysfs::read_string(dir.join("scaling_driver")),
+            governor: sysfs::read_string(dir.join("scaling_governor")),
+            energy_performance_preference: sysfs::read_string(
+                dir.join("energy_performance_preference"),
+            ),
+            // newer kernels expose boost per policy, the cpufreq dir is a link to it
+            boost: sysfs::read_bool(dir.join("boost")).or(global_boost),
+        })
+    }
+
+    fn global_boost(root: &Path) -> Option<bool> {
+        let cpu_dir = root.join("sys/devices/system/cpu");
+
+        // acpi-cpufreq, amd-pstate and most arm drivers
+        if let Some(boost) = sysfs::read_bool(cpu_dir.join("cpufreq/boost")) {
+            return Some(boost);
+        }
+
+        // intel_pstate reports the inverse
+        sysfs::read_bool(cpu_dir.join("intel_pstate/no_turbo")).map(|no_turbo| !no_turbo)
+    }
+}
@@ -0,0 +1,306 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::cpu::{CpuTopology, ProcCpuInfo};
+use crate::sysfs;
+
+/// Kind of core on a hybrid cpu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoreType {
+    Performance,
+    Efficiency,
+}
+
+/// Split of the logical cpus into performance and efficiency cores
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HybridTopology {
+    /// Logical cpus belonging to performance cores
+    pub p_cpus: Vec<u32>,
+    /// Logical cpus belonging to efficiency cores
+    pub e_cpus: Vec<u32>,
+    /// Number of physical performance cores
+    pub p_cores: u32,
+    /// Number of physical efficiency cores
+    pub e_cores: u32,
+    /// Highest frequency of the performance cluster in MHz
+    pub p_max_frequency: Option<u32>,
+    /// Highest frequency of the efficiency cluster in MHz
+    pub e_max_frequency: Option<u32>,
+}
+
+impl HybridTopology {
+    /// Detects the core types from the sysfs tree under `root`.
+    ///
+    /// Intel hybrid parts expose a `cpu_core` and a `cpu_atom` PMU, on ARM the clusters are told
+    /// apart by `cpu_capacity` and then by the max frequency. The lowest ranked cluster is
+    /// reported as the efficiency cores, every other cluster counts as performance cores.
+    /// On homogeneous machines all cores are reported as performance cores.
+    ///
+    /// x86 only trusts the PMUs: Turbo Boost Max 3.0 and amd-pstate preferred cores give some
+    /// cores of a homogeneous part a higher max frequency, and recent kernels derive
+    /// `cpu_capacity` from it.
+    pub fn detect<P: AsRef<Path>>(root: P) -> Self {
+        let root = root.as_ref();
+        // offline cpus keep their directory but may lose cpufreq, which would break the ranking
+        let cpus = sysfs::online_cpus(root);
+
+        let (p_cpus, e_cpus) = Self::from_pmu(root, &cpus)
+            .or_else(|| (!Self::is_x86(root)).then(|| Self::from_clusters(root, &cpus))?)
+            .unwrap_or((cpus, Vec::new()));
+
+        let topology = CpuTopology::from_root(root);
+
+        HybridTopology {
+            p_cores: topology.count_cores_of(&p_cpus),
+            e_cores: topology.count_cores_of(&e_cpus),
+            p_max_frequency: p_cpus
+                .iter()
+                .filter_map(|&cpu| Self::max_frequency(root, cpu))
+                .max(),
+            e_max_frequency: e_cpus
+                .iter()
+                .filter_map(|&cpu| Self::max_frequency(root, cpu))
+                .max(),
+            p_cpus,
+            e_cpus,
+        }
+    }
+
+    pub fn is_heterogeneous(&self) -> bool {
+        !self.p_cpus.is_empty() && !self.e_cpus.is_empty()
+    }
+
+    /// From the `vendor_id` lines of /proc/cpuinfo, or the build target if it is unreadable
+    fn is_x86(root: &Path) -> bool {
+        match ProcCpuInfo::from_root(root) {
+            Ok(cpuinfo) if !cpuinfo.processors.is_empty() => {
+                cpuinfo.processors.iter().any(|p| p.vendor_id.is_some())
+            }
+            _ => cfg!(any(target_arch = "x86", target_arch = "x86_64")),
+        }
+    }
+
+    /// The PMU masks, limited to the online `cpus`
+    fn from_pmu(root: &Path, cpus: &[u32]) -> Option<(Vec<u32>, Vec<u32>)> {
+        let pmu_cpus = |pmu: &str| {
+            let list = sysfs::read_string(root.join(format!("sys/devices/{}/cpus", pmu)))?;
+            Some(
+                sysfs::parse_cpu_list(&list)
+                    .into_iter()
+                    .filter(|cpu| cpus.contains(cpu))
+                    .collect::<Vec<u32>>(),
+            )
+        };
+
+        Some((pmu_cpus("cpu_core")?, pmu_cpus("cpu_atom")?))
+    }
+
+    /// By `cpu_capacity`, or the max frequency on kernels that do not expose it
+    fn from_clusters(root: &Path, cpus: &[u32]) -> Option<(Vec<u32>, Vec<u32>)> {
+        Self::split_by(cpus, |cpu| Self::capacity(root, cpu))
+            .or_else(|| Self::split_by(cpus, |cpu| Self::max_frequency(root, cpu).map(u64::from)))
+    }
+
+    /// Splits the cpus on the lowest value of `rank`, `None` if all cpus rank the same
+    fn split_by<F>(cpus: &[u32], rank: F) -> Option<(Vec<u32>, Vec<u32>)>
+    where
+        F: Fn(u32) -> Option<u64>,
+    {
+        let ranked: Vec<(u32, u64)> = cpus
+            .iter()
+            .map(|&cpu| rank(cpu).map(|r| (cpu, r)))
+            .collect::<Option<Vec<_>>>()?;
+
+        let lowest = ranked.iter().map(|(_, r)| *r).min()?;
+        let (e_cpus, p_cpus): (Vec<_>, Vec<_>) = ranked.iter().partition(|(_, r)| *r == lowest);
+
+        if p_cpus.is_empty() {
+            return None;
+        }
+
+        Some((
+            p_cpus.into_iter().map(|(cpu, _)| cpu).collect(),
+            e_cpus.into_iter().map(|(cpu, _)| cpu).collect(),
+        ))
+    }
+
+    fn capacity(root: &Path, cpu: u32) -> Option<u64> {
+        sysfs::read_u64(root.join(format!("sys/devices/system/cpu/cpu{}/cpu_capacity", cpu)))
+    }
+
+    /// in MHz
+    fn max_frequency(root: &Path, cpu: u32) -> Option<u32> {
+        sysfs::read_u64(root.join(format!(
+            "sys/devices/system/cpu/cpu{}/cpufreq/cpuinfo_max_freq",
+            cpu
+        )))
+        .map(|khz| (khz / 1000) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, FakeRoot};
+
+    /// One thread per core, with the given max frequency in MHz and optional capacity
+    fn add_cpus(root: &FakeRoot, cpus: &[(u32, u32, Option<u32>)]) {
+        for &(cpu, mhz, capacity) in cpus {
+            let dir = format!("sys/devices/system/cpu/cpu{}", cpu);
+            root.write(&format!("{}/topology/core_id", dir), &cpu.to_string())
+                .write(&format!("{}/topology/physical_package_id", dir), "0")
+                .write(
+                    &format!("{}/topology/thread_siblings_list", dir),
+                    &cpu.to_string(),
+                )
+                .write(
+                    &format!("{}/cpufreq/cpuinfo_max_freq", dir),
+                    &(mhz * 1000).to_string(),
+                );
+            if let Some(capacity) = capacity {
+                root.write(&format!("{}/cpu_capacity", dir), &capacity.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn splits_intel_hybrid_by_pmu() {
+        let root = FakeRoot::new("hybrid-pmu");
+        root.write("proc/cpuinfo", &fixture("cpuinfo/alderlake.txt"))
+            .write("sys/devices/cpu_core/cpus", "0-3\n")
+            .write("sys/devices/cpu_atom/cpus", "4-7\n");
+        add_cpus(
+            &root,
+            &[
+                (0, 4700, None),
+                (1, 4700, None),
+                (2, 4700, None),
+                (3, 4700, None),
+                (4, 3500, None),
+                (5, 3500, None),
+                (6, 3500, None),
+                (7, 3500, None),
+            ],
+        );
+
+        let hybrid = HybridTopology::detect(root.path());
+        assert!(hybrid.is_heterogeneous());
+        assert_eq!(hybrid.p_cpus, vec![0, 1, 2, 3]);
+        assert_eq!(hybrid.e_cpus, vec![4, 5, 6, 7]);
+        assert_eq!(hybrid.p_max_frequency, Some(4700));
+        assert_eq!(hybrid.e_max_frequency, Some(3500));
+    }
+
+    #[test]
+    fn ignores_preferred_cores_on_x86() {
+        // two cores boost higher than the rest, as with Turbo Boost Max 3.0
+        let root = FakeRoot::new("hybrid-x86");
+        root.write("proc/cpuinfo", &fixture("cpuinfo/x86_64.txt"));
+        add_cpus(
+            &root,
+            &[
+                (0, 3700, Some(1024)),
+                (1, 3700, Some(1024)),
+                (2, 3550, Some(982)),
+                (3, 3550, Some(982)),
+            ],
+        );
+
+        let hybrid = HybridTopology::detect(root.path());
+        assert!(!hybrid.is_heterogeneous());
+        assert_eq!(hybrid.p_cpus, vec![0, 1, 2, 3]);
+        assert_eq!(hybrid.p_cores, 4);
+    }
+
+    #[test]
+    fn splits_arm_clusters() {
+        let root = FakeRoot::new("hybrid-arm");
+        root.write("proc/cpuinfo", &fixture("cpuinfo/rk3588.txt"));
+        add_cpus(
+            &root,
+            &[
+                (0, 1800, Some(414)),
+                (1, 1800, Some(414)),
+                (2, 1800, Some(414)),
+                (3, 1800, Some(414)),
+                (4, 2400, Some(1024)),
+                (5, 2400, Some(1024)),
+                (6, 2256, Some(1024)),
+                (7, 2256, Some(1024)),
+            ],
+        );
+
+        let hybrid = HybridTopology::detect(root.path());
+        assert_eq!(hybrid.p_cpus, vec![4, 5, 6, 7]);
+        assert_eq!(hybrid.e_cpus, vec![0, 1, 2, 3]);
+        assert_eq!((hybrid.p_cores, hybrid.e_cores), (4, 4));
+        assert_eq!(hybrid.p_max_frequency, Some(2400));
+    }
+
+    #[test]
+    fn falls_back_to_frequency_on_arm() {
+        let root = FakeRoot::new("hybrid-arm-freq");
+        root.write("proc/cpuinfo", &fixture("cpuinfo/rk3588.txt"));
+        add_cpus(
+            &root,
+            &[
+                (0, 1800, None),
+                (1, 1800, None),
+                (2, 2400, None),
+                (3, 2400, None),
+            ],
+        );
+
+        let hybrid = HybridTopology::detect(root.path());
+        assert_eq!(hybrid.p_cpus, vec![2, 3]);
+        assert_eq!(hybrid.e_cpus, vec![0, 1]);
+    }
+
+    #[test]
+    fn skips_offline_cpus() {
+        let root = FakeRoot::new("hybrid-offline");
+        root.write("proc/cpuinfo", &fixture("cpuinfo/rk3588.txt"))
+            .write("sys/devices/system/cpu/online", "0-4\n");
+        add_cpus(
+            &root,
+            &[
+                (0, 1800, None),
+                (1, 1800, None),
+                (2, 1800, None),
+                (3, 2400, None),
+                (4, 2400, None),
+            ],
+        );
+        // an offline E-core keeps its topology but has no cpufreq policy
+        root.write("sys/devices/system/cpu/cpu5/topology/core_id", "5");
+
+        let hybrid = HybridTopology::detect(root.path());
+        assert_eq!(hybrid.p_cpus, vec![3, 4]);
+        assert_eq!(hybrid.e_cpus, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn limits_pmu_masks_to_online_cpus() {
+        let root = FakeRoot::new("hybrid-pmu-offline");
+        root.write("proc/cpuinfo", &fixture("cpuinfo/alderlake.txt"))
+            .write("sys/devices/system/cpu/online", "0-2,4-5\n")
+            .write("sys/devices/cpu_core/cpus", "0-3\n")
+            .write("sys/devices/cpu_atom/cpus", "4-7\n");
+        add_cpus(
+            &root,
+            &[
+                (0, 4700, None),
+                (1, 4700, None),
+                (2, 4700, None),
+                (4, 3500, None),
+                (5, 3500, None),
+            ],
+        );
+
+        let hybrid = HybridTopology::detect(root.path());
+        assert_eq!(hybrid.p_cpus, vec![0, 1, 2]);
+        assert_eq!(hybrid.e_cpus, vec![4, 5]);
+        assert_eq!((hybrid.p_cores, hybrid.e_cores), (3, 2));
+    }
+}
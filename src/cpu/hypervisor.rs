@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::sysfs;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Hypervisor {
+    Kvm,
+    HyperV,
+    VMware,
+    Xen,
+    Qemu,
+    VirtualBox,
+    Parallels,
+    Bhyve,
+    Acrn,
+    /// Detected but not recognised, holds the raw signature
+    Other(String),
+}
+
+impl Hypervisor {
+    /// Detects the hypervisor the current system runs under, `None` on bare metal. Outside of
+    /// Linux only CPUID is consulted.
+    pub fn detect() -> Option<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            Self::from_cpuid().or_else(|| Self::from_root("/"))
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            Self::from_cpuid()
+        }
+    }
+
+    /// Maps the 12 byte vendor signature of CPUID leaf 0x40000000
+    pub fn from_signature(signature: &str) -> Self {
+        match signature.trim_end_matches('\0') {
+            "KVMKVMKVM" => Hypervisor::Kvm,
+            "Microsoft Hv" => Hypervisor::HyperV,
+            "VMwareVMware" => Hypervisor::VMware,
+            "XenVMMXenVMM" => Hypervisor::Xen,
+            "TCGTCGTCGTCG" => Hypervisor::Qemu,
+            "VBoxVBoxVBox" => Hypervisor::VirtualBox,
+            " lrpepyh  vr" | "prl hyperv  " => Hypervisor::Parallels,
+            "bhyve bhyve " => Hypervisor::Bhyve,
+            "ACRNACRNACRN" => Hypervisor::Acrn,
+            other => Hypervisor::Other(other.trim().to_string()),
+        }
+    }
+
+    /// Detection through /sys/hypervisor (Xen PV guests) and the DMI system strings,
+    /// which is the only option on non x86 guests
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Option<Self> {
+        let root = root.as_ref();
+
+        if let Some(kind) = sysfs::read_string(root.join("sys/hypervisor/type")) {
+            return Some(match kind.as_str() {
+                "xen" => Hypervisor::Xen,
+                _ => Hypervisor::Other(kind),
+            });
+        }
+
+        let dmi = root.join("sys/devices/virtual/dmi/id");
+        let sys_vendor = sysfs::read_string(dmi.join("sys_vendor")).unwrap_or_default();
+        let product_name = sysfs::read_string(dmi.join("product_name")).unwrap_or_default();
+
+        match (sys_vendor.as_str(), product_name.as_str()) {
+            ("QEMU", _) => Some(Hypervisor::Qemu),
+            (_, "KVM") => Some(Hypervisor::Kvm),
+            // Nitro is KVM based, bare metal instances are named `*.metal`
+            ("Amazon EC2", product) if !product.ends_with(".metal") => Some(Hypervisor::Kvm),
+            ("Microsoft Corporation", "Virtual Machine") => Some(Hypervisor::HyperV),
+            ("VMware, Inc.", _) => Some(Hypervisor::VMware),
+            ("Xen", _) => Some(Hypervisor::Xen),
+            ("innotek GmbH", _) => Some(Hypervisor::VirtualBox),
+            ("Parallels Software International Inc.", _) => Some(Hypervisor::Parallels),
+            _ => None,
+        }
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn from_cpuid() -> Option<Self> {
+        use super::x86::cpuid;
+
+        // CPUID.1:ECX bit 31 is reserved for hypervisors to announce themselves
+        let present = cpuid(1, 0).map(|r| r.ecx & (1 << 31) != 0)?;
+        if !present {
+            return None;
+        }
+
+        let leaf = cpuid(0x4000_0000, 0)?;
+        let bytes: Vec<u8> = [leaf.ebx, leaf.ecx, leaf.edx]
+            .iter()
+            .flat_map(|r| r.to_le_bytes())
+            .collect();
+
+        Some(Self::from_signature(&String::from_utf8_lossy(&bytes)))
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    fn from_cpuid() -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    #[test]
+    fn maps_cpuid_signatures() {
+        assert_eq!(
+            Hypervisor::from_signature("KVMKVMKVM\0\0\0"),
+            Hypervisor::Kvm
+        );
+        assert_eq!(
+            Hypervisor::from_signature("Microsoft Hv"),
+            Hypervisor::HyperV
+        );
+        assert_eq!(
+            Hypervisor::from_signature("Unknown VMM "),
+            Hypervisor::Other("Unknown VMM".to_string())
+        );
+    }
+
+    #[test]
+    fn detects_from_dmi() {
+        let root = FakeRoot::new("hypervisor-dmi");
+        assert_eq!(Hypervisor::from_root(root.path()), None);
+
+        root.write("sys/devices/virtual/dmi/id/sys_vendor", "Amazon EC2\n")
+            .write("sys/devices/virtual/dmi/id/product_name", "c7g.large\n");
+        assert_eq!(Hypervisor::from_root(root.path()), Some(Hypervisor::Kvm));
+
+        root.write("sys/devices/virtual/dmi/id/product_name", "c7g.metal\n");
+        assert_eq!(Hypervisor::from_root(root.path()), None);
+
+        root.write("sys/hypervisor/type", "xen\n");
+        assert_eq!(Hypervisor::from_root(root.path()), Some(Hypervisor::Xen));
+    }
+}
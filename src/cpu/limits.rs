@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::cgroup::{Cgroup, CgroupVersion};
+use crate::sysfs;
+
+/// CPU restrictions applied to the current process by cgroups and the scheduler affinity
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuLimits {
+    pub cgroup_version: Option<CgroupVersion>,
+    /// CFS bandwidth limit in cpus (quota / period), e.g. 1.5 for `150000 100000`
+    pub quota: Option<f64>,
+    /// cpus allowed by the cpuset controller
+    pub cpuset: Option<Vec<u32>>,
+    /// cpus in the scheduler affinity mask of the process
+    pub affinity: Option<Vec<u32>>,
+    /// Online cpus of the machine
+    pub online: Vec<u32>,
+}
+
+impl CpuLimits {
+    /// Reads the cgroup limits of the current process under `root`. The affinity mask can
+    /// only be queried for the running process, so it is passed in by the caller.
+    pub fn from_root<P: AsRef<Path>>(root: P, affinity: Option<Vec<u32>>) -> Self {
+        let root = root.as_ref();
+        let cgroup = Cgroup::current(root);
+
+        let cpu_dirs = cgroup.as_ref().and_then(|c| c.controller_dirs("cpu"));
+        let cpuset_dirs = cgroup.as_ref().and_then(|c| c.controller_dirs("cpuset"));
+
+        let quota = cpu_dirs
+            .as_ref()
+            .and_then(|(version, dirs)| Self::read_quota(*version, dirs));
+
+        let cpuset = cpuset_dirs
+            .as_ref()
+            .and_then(|(version, dirs)| {
+                let file = match version {
+                    CgroupVersion::V1 => "cpuset.effective_cpus",
+                    CgroupVersion::V2 => "cpuset.cpus.effective",
+                };
+                // the effective list already accounts for the ancestors, it is only missing
+                // in cgroups that do not have the controller enabled
+                dirs.iter()
+                    .find_map(|dir| sysfs::read_string(dir.join(file)))
+                    .map(|list| sysfs::parse_cpu_list(&list))
+            })
+            .filter(|cpus| !cpus.is_empty());
+
+        let online = sysfs::online_cpus(root);
+
+        CpuLimits {
+            cgroup_version: cpu_dirs.or(cpuset_dirs).map(|(version, _)| version),
+            quota,
+            cpuset,
+            affinity: affinity.filter(|cpus| !cpus.is_empty()),
+            online,
+        }
+    }
+
+    /// Smallest quota / period ratio of the cgroup and its ancestors
+    fn read_quota(version: CgroupVersion, dirs: &[PathBuf]) -> Option<f64> {
+        dirs.iter()
+            .filter_map(|dir| match version {
+                // `max 100000` when unlimited
+                CgroupVersion::V2 => {
+                    let content = sysfs::read_string(dir.join("cpu.max"))?;
+                    let mut parts = content.split_whitespace();
+                    let quota = parts.next()?.parse::<f64>().ok()?;
+                    let period = parts.next()?.parse::<f64>().ok()?;
+                    Some(quota / period)
+                }
+                // quota is -1 when unlimited
+                CgroupVersion::V1 => {
+                    let quota = sysfs::read_string(dir.join("cpu.cfs_quota_us"))?
+                        .parse::<i64>()
+                        .ok()
+                        .filter(|quota| *quota > 0)?;
+                    let period = sysfs::read_u64(dir.join("cpu.cfs_period_us"))?;
+                    Some(quota as f64 / period as f64)
+                }
+            })
+            .filter(|cpus| cpus.is_finite() && *cpus > 0.0)
+            .reduce(f64::min)
+    }
+
+    /// Number of cpus the process can actually keep busy: the smallest of the online
+    /// cpus, the cpuset, the affinity mask and the quota rounded up, at least 1.
+    /// `None` when nothing could be read.
+    pub fn effective_cpus(&self) -> Option<u32> {
+        [
+            (!self.online.is_empty()).then_some(self.online.len() as u32),
+            self.cpuset.as_ref().map(|cpus| cpus.len() as u32),
+            self.affinity.as_ref().map(|cpus| cpus.len() as u32),
+            self.quota.map(|quota| quota.ceil() as u32),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .map(|cpus| cpus.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    #[test]
+    fn reads_v2_limits() {
+        let root = FakeRoot::new("cpu-limits-v2");
+        root.write("proc/self/cgroup", "0::/kubepods/pod1\n")
+            .write("sys/devices/system/cpu/online", "0-7\n")
+            .write("sys/fs/cgroup/cgroup.controllers", "cpuset cpu memory\n")
+            .write("sys/fs/cgroup/cpu.max", "max 100000\n")
+            .write("sys/fs/cgroup/kubepods/cpu.max", "250000 100000\n")
+            .write("sys/fs/cgroup/kubepods/pod1/cpu.max", "max 100000\n")
+            .write("sys/fs/cgroup/kubepods/pod1/cpuset.cpus.effective", "0-5\n");
+
+        let limits = CpuLimits::from_root(root.path(), Some((0..8).collect()));
+        assert_eq!(limits.cgroup_version, Some(CgroupVersion::V2));
+        // the pod is unlimited, its parent is not
+        assert_eq!(limits.quota, Some(2.5));
+        assert_eq!(limits.cpuset, Some(vec![0, 1, 2, 3, 4, 5]));
+        assert_eq!(limits.online.len(), 8);
+        assert_eq!(limits.effective_cpus(), Some(3));
+    }
+
+    #[test]
+    fn takes_the_smallest_ancestor_quota() {
+        let root = FakeRoot::new("cpu-limits-ancestors");
+        root.write("proc/self/cgroup", "0::/a/b\n")
+            .write("sys/fs/cgroup/cgroup.controllers", "cpu\n")
+            .write("sys/fs/cgroup/a/cpu.max", "50000 100000\n")
+            .write("sys/fs/cgroup/a/b/cpu.max", "400000 100000\n");
+
+        let limits = CpuLimits::from_root(root.path(), None);
+        assert_eq!(limits.quota, Some(0.5));
+        // cpus come from the cpuN directories without an online list, none here
+        assert!(limits.online.is_empty());
+        assert_eq!(limits.effective_cpus(), Some(1));
+    }
+
+    #[test]
+    fn reads_v1_limits() {
+        let root = FakeRoot::new("cpu-limits-v1");
+        root.write(
+            "proc/self/cgroup",
+            "5:cpuset:/docker/abc\n4:cpu,cpuacct:/docker/abc\n0::/\n",
+        )
+        .write("sys/devices/system/cpu/online", "0-15\n")
+        .write("sys/fs/cgroup/cpu,cpuacct/cpu.cfs_quota_us", "-1\n")
+        .write("sys/fs/cgroup/cpu,cpuacct/cpu.cfs_period_us", "100000\n")
+        .write(
+            "sys/fs/cgroup/cpu,cpuacct/docker/abc/cpu.cfs_quota_us",
+            "-1\n",
+        )
+        .write(
+            "sys/fs/cgroup/cpu,cpuacct/docker/abc/cpu.cfs_period_us",
+            "100000\n",
+        )
+        .write(
+            "sys/fs/cgroup/cpuset/docker/abc/cpuset.effective_cpus",
+            "2-5,8\n",
+        );
+
+        let limits = CpuLimits::from_root(root.path(), None);
+        assert_eq!(limits.cgroup_version, Some(CgroupVersion::V1));
+        // -1 means unlimited
+        assert_eq!(limits.quota, None);
+        assert_eq!(limits.cpuset, Some(vec![2, 3, 4, 5, 8]));
+        assert_eq!(limits.effective_cpus(), Some(5));
+    }
+
+    #[test]
+    fn rounds_the_quota_up() {
+        let limits = |quota: Option<f64>, cpuset: Option<Vec<u32>>, affinity: Option<Vec<u32>>| {
+            CpuLimits {
+                quota,
+                cpuset,
+                affinity,
+                online: (0..8).collect(),
+                ..Default::default()
+            }
+            .effective_cpus()
+        };
+
+        assert_eq!(limits(None, None, None), Some(8));
+        assert_eq!(limits(Some(0.2), None, None), Some(1));
+        assert_eq!(limits(Some(2.5), Some(vec![0, 1, 2, 3]), None), Some(3));
+        assert_eq!(limits(Some(6.0), Some(vec![0, 1, 2, 3]), None), Some(4));
+        assert_eq!(
+            limits(None, Some(vec![0, 1, 2, 3]), Some(vec![1, 2])),
+            Some(2)
+        );
+        assert_eq!(CpuLimits::default().effective_cpus(), None);
+    }
+}
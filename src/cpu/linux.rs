@@ -1,39 +1,74 @@
-use crate::cpu::{CPUData, CPUUsage, CpuFeatureSet};
-use std::process::Command;
+use crate::cpu::{
+    CPUData, CPUUsage, CpuCache, CpuFeatureSet, CpuFrequency, CpuLimits, CpuThrottleInfo,
+    Hypervisor, ProcCpuInfo,
+};
 use sysinfo::{CpuRefreshKind, RefreshKind, System};
 
-use super::{CPUArchitecture, CPUVendor};
+use super::{CPUArchitecture, CPUVendor, CpuTopology, LoadAverage};
 
 impl CPUUsage {
     pub fn get_cpu_info() -> Result<CPUData, Box<dyn std::error::Error>> {
+        // a missing /proc/cpuinfo should not fail the whole query, sysinfo is used as fallback
+        let cpuinfo = ProcCpuInfo::from_root("/").unwrap_or_default();
+
         Ok(CPUData {
-            name: Self::get_name(),
-            vendor: Self::get_cpu_vendor(),
+            name: Self::get_name(&cpuinfo),
+            vendor: Self::get_cpu_vendor(&cpuinfo),
             architecture: Self::get_architecture(),
             num_of_cores: Self::num_of_cores(),
             logical_processors: Self::logical_processors(),
-            instruction_sets: Self::get_instruction_sets(),
-            average_cpu_usage: Self::average_usage(),
+            effective_cpus: Self::effective_cpus(),
+            instruction_sets: Self::get_instruction_sets(&cpuinfo),
+            load_average: Self::load_average(),
+            family: cpuinfo.family(),
+            model: cpuinfo.model(),
+            stepping: cpuinfo.stepping(),
+            microcode: cpuinfo.microcode(),
+            flags: cpuinfo.flags(),
+            frequencies: CpuFrequency::read_all("/"),
+            topology: CpuTopology::from_root("/"),
+            caches: CpuCache::read_all("/"),
+            microarchitecture: cpuinfo.microarchitecture(),
+            hypervisor: Hypervisor::detect(),
+            processors: cpuinfo.processors,
         })
     }
 
-    fn get_name() -> String {
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg("lscpu | grep 'Model name' | awk -F: '{print $2}'")
-            .output()
-            .expect("Failed to execute command");
+    fn get_name(cpuinfo: &ProcCpuInfo) -> String {
+        if let Some(name) = cpuinfo.model_name() {
+            return name;
+        }
 
-        String::from_utf8_lossy(&output.stdout).trim().to_string()
+        let s =
+            System::new_with_specifics(RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing()));
+        s.cpus()
+            .first()
+            .map(|cpu| cpu.brand().trim().to_string())
+            .unwrap_or_default()
     }
 
-    fn get_cpu_vendor() -> CPUVendor {
+    fn get_cpu_vendor(cpuinfo: &ProcCpuInfo) -> CPUVendor {
+        let vendor = cpuinfo.vendor();
+        if vendor == CPUVendor::Arm {
+            return CPUVendor::arm_licensee("/").unwrap_or(vendor);
+        }
+        if vendor != CPUVendor::Other {
+            return vendor;
+        }
+
         let s = System::new_with_specifics(
             RefreshKind::nothing().with_cpu(CpuRefreshKind::everything()),
         );
 
-        let vendor_id = s.cpus().get(0).unwrap().vendor_id();
-        CPUVendor::from_vendor_id(vendor_id)
+        s.cpus()
+            .first()
+            .map(|cpu| CPUVendor::from_vendor_id(cpu.vendor_id()))
+            .unwrap_or(CPUVendor::Other)
+    }
+
+    /// Thermal throttle counters, RAPL power limits / energy counters and frequency caps
+    pub fn get_throttle_info() -> Result<CpuThrottleInfo, Box<dyn std::error::Error>> {
+        Ok(CpuThrottleInfo::from_root("/"))
     }
 
     pub fn num_of_cores() -> u32 {
@@ -46,11 +81,40 @@ impl CPUUsage {
         sys.cpus().len() as u32
     }
 
-    pub fn average_usage() -> f32 {
-        match sys_info::loadavg() {
-            Ok(load) => load.one as f32,
-            Err(_) => 0.0,
+    /// cgroup cpu quota, cpuset and affinity restrictions of the current process
+    pub fn get_cpu_limits() -> Result<CpuLimits, Box<dyn std::error::Error>> {
+        Ok(CpuLimits::from_root("/", Self::affinity()))
+    }
+
+    /// Cpus the current process can use, falls back to `logical_processors` when the
+    /// limits cannot be read
+    pub fn effective_cpus() -> u32 {
+        CpuLimits::from_root("/", Self::affinity())
+            .effective_cpus()
+            .unwrap_or_else(Self::logical_processors)
+    }
+
+    fn affinity() -> Option<Vec<u32>> {
+        // SAFETY: cpu_set_t is a plain bitmask, zeroed is a valid empty set
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        // SAFETY: `set` is a valid cpu_set_t of the size passed in
+        let result =
+            unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) };
+        if result != 0 {
+            return None;
         }
+
+        Some(
+            (0..libc::CPU_SETSIZE as usize)
+                // SAFETY: CPU_ISSET only reads the bitmask, `cpu` is below CPU_SETSIZE
+                .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+                .map(|cpu| cpu as u32)
+                .collect(),
+        )
+    }
+
+    pub fn load_average() -> LoadAverage {
+        LoadAverage::current()
     }
 
     fn get_architecture() -> CPUArchitecture {
@@ -68,18 +132,16 @@ impl CPUUsage {
         }
     }
 
-    fn get_instruction_sets() -> Vec<CpuFeatureSet> {
-        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-        {
-            use strum::IntoEnumIterator;
-            CpuFeatureSet::iter()
-                .filter(|f| f.is_supported_x86())
-                .collect()
-        }
+    fn get_instruction_sets(cpuinfo: &ProcCpuInfo) -> Vec<CpuFeatureSet> {
+        use strum::IntoEnumIterator;
+        let detected: Vec<CpuFeatureSet> =
+            CpuFeatureSet::iter().filter(|f| f.is_supported()).collect();
 
-        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
-        {
-            Vec::new()
+        // targets without runtime detection (e.g. riscv64) rely on the kernel's view
+        if detected.is_empty() {
+            CpuFeatureSet::from_cpuinfo(cpuinfo)
+        } else {
+            detected
         }
     }
 }
@@ -1,8 +1,11 @@
-use crate::cpu::{CPUData, CPUUsage, CpuFeatureSet};
+use crate::cpu::{
+    CPUData, CPUUsage, CacheType, CoreType, CpuCache, CpuFeatureSet, CpuLimits, CpuThrottleInfo,
+    Hypervisor,
+};
 use std::process::Command;
 use sysinfo::{CpuRefreshKind, RefreshKind, System};
 
-use super::{CPUArchitecture, CPUVendor};
+use super::{CPUArchitecture, CPUVendor, CpuTopology, LoadAverage};
 
 impl CPUUsage {
     pub fn get_cpu_info() -> Result<CPUData, Box<dyn std::error::Error>> {
@@ -12,8 +15,20 @@ impl CPUUsage {
             architecture: Self::get_architecture(),
             num_of_cores: Self::num_of_cores(),
             logical_processors: Self::logical_processors(),
-            average_cpu_usage: Self::average_usage(),
+            effective_cpus: Self::effective_cpus(),
+            load_average: Self::load_average(),
             instruction_sets: Self::get_instruction_sets(),
+            family: None,
+            model: None,
+            stepping: None,
+            microcode: None,
+            flags: Vec::new(),
+            processors: Vec::new(),
+            frequencies: Vec::new(),
+            topology: CpuTopology::default(),
+            caches: Self::get_caches(),
+            microarchitecture: None,
+            hypervisor: Hypervisor::detect(),
         })
     }
 
@@ -27,6 +42,64 @@ impl CPUUsage {
             .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
 
+    fn get_sysctl_u64(key: &str) -> Option<u64> {
+        Self::get_sysctl_value(key)?.parse().ok()
+    }
+
+    /// Cache sizes per performance level on Apple Silicon (`hw.perflevelN.*`), the system
+    /// wide `hw.l*cachesize` values otherwise. Sharing is not reported.
+    fn get_caches() -> Vec<CpuCache> {
+        let line_size = Self::get_sysctl_u64("hw.cachelinesize").map(|v| v as u32);
+        let cache = |level: u32, cache_type: CacheType, key: &str, core_type: Option<CoreType>| {
+            Self::get_sysctl_u64(key)
+                .filter(|size| *size > 0)
+                .map(|size| CpuCache {
+                    level,
+                    cache_type,
+                    size,
+                    line_size,
+                    ways_of_associativity: None,
+                    shared_cpus: Vec::new(),
+                    core_type,
+                })
+        };
+        let levels = [
+            (1, CacheType::Instruction, "l1icachesize"),
+            (1, CacheType::Data, "l1dcachesize"),
+            (2, CacheType::Unified, "l2cachesize"),
+            (3, CacheType::Unified, "l3cachesize"),
+        ];
+
+        let perf_levels = Self::get_sysctl_u64("hw.nperflevels").unwrap_or(0);
+        if perf_levels < 2 {
+            return levels
+                .iter()
+                .filter_map(|(level, cache_type, name)| {
+                    cache(*level, *cache_type, &format!("hw.{}", name), None)
+                })
+                .collect();
+        }
+
+        (0..perf_levels)
+            .flat_map(|index| {
+                let core_type = match Self::get_sysctl_value(&format!("hw.perflevel{}.name", index))
+                    .as_deref()
+                {
+                    Some("Performance") => Some(CoreType::Performance),
+                    Some("Efficiency") => Some(CoreType::Efficiency),
+                    _ => None,
+                };
+                levels
+                    .iter()
+                    .filter_map(|(level, cache_type, name)| {
+                        let key = format!("hw.perflevel{}.{}", index, name);
+                        cache(*level, *cache_type, &key, core_type)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
     fn get_name() -> String {
         Self::get_sysctl_value("machdep.cpu.brand_string")
             .unwrap_or_else(|| "Unknown CPU".to_string())
@@ -72,6 +145,18 @@ impl CPUUsage {
             .unwrap_or(false)
     }
 
+    pub fn get_cpu_limits() -> Result<CpuLimits, Box<dyn std::error::Error>> {
+        Err("Cpu limits are not implemented for macOS".into())
+    }
+
+    pub fn effective_cpus() -> u32 {
+        Self::logical_processors()
+    }
+
+    pub fn get_throttle_info() -> Result<CpuThrottleInfo, Box<dyn std::error::Error>> {
+        Err("Throttle info is not implemented for macOS".into())
+    }
+
     pub fn num_of_cores() -> u32 {
         sysinfo::System::physical_core_count().unwrap_or(0) as u32
     }
@@ -82,8 +167,8 @@ impl CPUUsage {
         sys.cpus().len() as u32
     }
 
-    pub fn average_usage() -> f32 {
-        sys_info::loadavg().unwrap().one as f32
+    pub fn load_average() -> LoadAverage {
+        LoadAverage::current()
     }
 
     fn get_architecture() -> CPUArchitecture {
@@ -104,17 +189,7 @@ impl CPUUsage {
     }
 
     fn get_instruction_sets() -> Vec<CpuFeatureSet> {
-        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-        {
-            use strum::IntoEnumIterator;
-            CpuFeatureSet::iter()
-                .filter(|f| f.is_supported_x86())
-                .collect()
-        }
-
-        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
-        {
-            Vec::new()
-        }
+        use strum::IntoEnumIterator;
+        CpuFeatureSet::iter().filter(|f| f.is_supported()).collect()
     }
 }
@@ -1,6 +1,29 @@
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 
+mod arm;
+mod cache;
+mod cpuinfo;
+mod frequency;
+mod hybrid;
+mod hypervisor;
+mod limits;
+mod power;
+mod stat;
+mod topology;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86;
+
+pub use cache::{CacheType, CpuCache};
+pub use cpuinfo::{ProcCpuInfo, ProcessorInfo};
+pub use frequency::CpuFrequency;
+pub use hybrid::{CoreType, HybridTopology};
+pub use hypervisor::Hypervisor;
+pub use limits::CpuLimits;
+pub use power::{CpuThrottleInfo, PowerConstraint, PowerZone, ThrottleCounters};
+pub use stat::{CpuSampler, CpuTimes, CpuUsageSample, CpuUtilization, ProcStat};
+pub use topology::{CpuCluster, CpuCore, CpuDie, CpuPackage, CpuTopology};
+
 #[cfg(target_os = "macos")]
 mod macos;
 #[cfg(target_os = "windows")]
@@ -18,8 +41,55 @@ pub struct CPUData {
     pub architecture: CPUArchitecture,
     pub num_of_cores: u32,
     pub logical_processors: u32,
+    /// Cpus the process can use after cgroup quotas, cpusets and the affinity mask,
+    /// equal to `logical_processors` outside of Linux
+    pub effective_cpus: u32,
     pub instruction_sets: Vec<CpuFeatureSet>,
-    pub average_cpu_usage: f32,
+    pub load_average: LoadAverage,
+    pub family: Option<u32>,
+    pub model: Option<u32>,
+    pub stepping: Option<u32>,
+    pub microcode: Option<String>,
+    pub flags: Vec<String>,
+    /// Per logical processor entries, only populated on Linux
+    pub processors: Vec<ProcessorInfo>,
+    /// Per logical processor clock data, only populated on Linux
+    pub frequencies: Vec<CpuFrequency>,
+    /// Package / die / cluster / core / SMT layout, only populated on Linux
+    pub topology: CpuTopology,
+    /// Distinct cache instances, on macOS this comes from the soc database
+    pub caches: Vec<CpuCache>,
+    /// Core design, e.g. `Neoverse-V2`, only populated for ARM on Linux
+    pub microarchitecture: Option<String>,
+    pub hypervisor: Option<Hypervisor>,
+}
+
+impl CPUData {
+    /// x86-64 micro-architecture level of the detected instruction sets
+    pub fn feature_level(&self) -> Option<CpuFeatureLevel> {
+        CpuFeatureLevel::from_features(&self.instruction_sets)
+    }
+}
+
+/// Average number of runnable tasks over 1, 5 and 15 minutes, this is not a percentage,
+/// use `CpuSampler` for utilization
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+impl LoadAverage {
+    pub(crate) fn current() -> Self {
+        sys_info::loadavg()
+            .map(|load| LoadAverage {
+                one: load.one,
+                five: load.five,
+                fifteen: load.fifteen,
+            })
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
@@ -65,9 +135,271 @@ pub enum CpuFeatureSet {
     AvxNeConvert,
     AvxVnniInt8,
     AvxVnniInt16,
+
+    // ===== FMA / HALF PRECISION =====
+    Fma,
+    F16c,
+
+    // ===== BIT MANIPULATION / MISC =====
+    Popcnt,
+    Lzcnt,
+    Bmi1,
+    Bmi2,
+    Movbe,
+    Cmpxchg16b,
+
+    // ===== x86 CRYPTO / GALOIS FIELD =====
+    Sha,
+    Vaes,
+    Vpclmulqdq,
+    Gfni,
+
+    // ===== AMX =====
+    AmxTile,
+    AmxInt8,
+    AmxBf16,
+    AmxFp16,
+
+    // ===== APX =====
+    Apx,
+
+    // ===== ARM SIMD =====
+    Neon,
+    Fp16,
+    Fhm,
+    DotProd,
+    I8mm,
+    Bf16,
+    Sve,
+    Sve2,
+
+    // ===== ARM CRYPTO / MISC =====
+    /// AES-NI on x86
+    Aes,
+    Pmull,
+    Sha2,
+    Sha3,
+    Crc32,
+    Lse,
+
+    // ===== RISC-V =====
+    RvM,
+    RvA,
+    RvF,
+    RvD,
+    RvC,
+    /// Vector extension (RVV 1.0)
+    RvV,
+    Zba,
+    Zbb,
+    Zbc,
+    Zbs,
+    Zfh,
+    Zvfh,
 }
 
 impl CpuFeatureSet {
+    /// Runtime detection for the current target, RISC-V has no stable runtime
+    /// detection so it always reports false there, use `from_cpuinfo` instead
+    pub fn is_supported(&self) -> bool {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            self.is_supported_x86()
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            self.is_supported_aarch64()
+        }
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            false
+        }
+    }
+
+    /// Features listed in the ARM `Features` or the RISC-V `isa` line of /proc/cpuinfo
+    pub fn from_cpuinfo(cpuinfo: &ProcCpuInfo) -> Vec<CpuFeatureSet> {
+        let processor = match cpuinfo.processors.first() {
+            Some(processor) => processor,
+            None => return Vec::new(),
+        };
+
+        let mut features: Vec<CpuFeatureSet> = match &processor.isa {
+            Some(isa) => Self::from_riscv_isa(isa),
+            // x86 `flags` share names such as `aes` with the ARM list
+            None if processor.implementer.is_some() => processor
+                .flags
+                .iter()
+                .filter_map(|flag| Self::from_arm_feature(flag))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        features.sort_by_key(|f| *f as u32);
+        features.dedup();
+        features
+    }
+
+    fn from_arm_feature(feature: &str) -> Option<CpuFeatureSet> {
+        match feature {
+            // `neon` is printed by 32 bit kernels, `asimd` by 64 bit ones
+            "asimd" | "neon" => Some(CpuFeatureSet::Neon),
+            "asimdhp" => Some(CpuFeatureSet::Fp16),
+            "asimdfhm" => Some(CpuFeatureSet::Fhm),
+            "asimddp" => Some(CpuFeatureSet::DotProd),
+            "i8mm" => Some(CpuFeatureSet::I8mm),
+            "bf16" => Some(CpuFeatureSet::Bf16),
+            "sve" => Some(CpuFeatureSet::Sve),
+            "sve2" => Some(CpuFeatureSet::Sve2),
+            "aes" => Some(CpuFeatureSet::Aes),
+            "pmull" => Some(CpuFeatureSet::Pmull),
+            "sha2" => Some(CpuFeatureSet::Sha2),
+            "sha3" => Some(CpuFeatureSet::Sha3),
+            "crc32" => Some(CpuFeatureSet::Crc32),
+            "atomics" => Some(CpuFeatureSet::Lse),
+            _ => None,
+        }
+    }
+
+    /// Parses an isa string such as `rv64imafdcv_zicsr_zba_zbb`
+    fn from_riscv_isa(isa: &str) -> Vec<CpuFeatureSet> {
+        let isa = isa.to_lowercase();
+        let isa = match isa
+            .strip_prefix("rv64")
+            .or_else(|| isa.strip_prefix("rv32"))
+        {
+            Some(rest) => rest,
+            None => return Vec::new(),
+        };
+
+        let mut parts = isa.split('_');
+        let base = parts.next().unwrap_or_default();
+
+        // multi letter extensions may directly follow the single letter ones
+        let (letters, first_extension) = match base.find(['z', 's', 'x']) {
+            Some(index) => (&base[..index], Some(&base[index..])),
+            None => (base, None),
+        };
+
+        let mut features = Vec::new();
+        for letter in letters.chars() {
+            match letter {
+                'g' => features.extend([
+                    CpuFeatureSet::RvM,
+                    CpuFeatureSet::RvA,
+                    CpuFeatureSet::RvF,
+                    CpuFeatureSet::RvD,
+                ]),
+                'm' => features.push(CpuFeatureSet::RvM),
+                'a' => features.push(CpuFeatureSet::RvA),
+                'f' => features.push(CpuFeatureSet::RvF),
+                'd' => features.push(CpuFeatureSet::RvD),
+                'c' => features.push(CpuFeatureSet::RvC),
+                'v' => features.push(CpuFeatureSet::RvV),
+                _ => {}
+            }
+        }
+
+        for extension in first_extension.into_iter().chain(parts) {
+            match extension {
+                "zba" => features.push(CpuFeatureSet::Zba),
+                "zbb" => features.push(CpuFeatureSet::Zbb),
+                "zbc" => features.push(CpuFeatureSet::Zbc),
+                "zbs" => features.push(CpuFeatureSet::Zbs),
+                "zfh" => features.push(CpuFeatureSet::Zfh),
+                "zvfh" => features.push(CpuFeatureSet::Zvfh),
+                _ => {}
+            }
+        }
+
+        features
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn is_supported_aarch64(&self) -> bool {
+        use std::arch::is_aarch64_feature_detected;
+
+        match self {
+            CpuFeatureSet::Neon => is_aarch64_feature_detected!("neon"),
+            CpuFeatureSet::Fp16 => is_aarch64_feature_detected!("fp16"),
+            CpuFeatureSet::Fhm => is_aarch64_feature_detected!("fhm"),
+            CpuFeatureSet::DotProd => is_aarch64_feature_detected!("dotprod"),
+            CpuFeatureSet::I8mm => is_aarch64_feature_detected!("i8mm"),
+            CpuFeatureSet::Bf16 => is_aarch64_feature_detected!("bf16"),
+            CpuFeatureSet::Sve => is_aarch64_feature_detected!("sve"),
+            CpuFeatureSet::Sve2 => is_aarch64_feature_detected!("sve2"),
+
+            CpuFeatureSet::Aes => is_aarch64_feature_detected!("aes"),
+            CpuFeatureSet::Pmull => is_aarch64_feature_detected!("pmull"),
+            CpuFeatureSet::Sha2 => is_aarch64_feature_detected!("sha2"),
+            CpuFeatureSet::Sha3 => is_aarch64_feature_detected!("sha3"),
+            CpuFeatureSet::Crc32 => is_aarch64_feature_detected!("crc"),
+            CpuFeatureSet::Lse => is_aarch64_feature_detected!("lse"),
+
+            CpuFeatureSet::Sse
+            | CpuFeatureSet::Sse2
+            | CpuFeatureSet::Sse3
+            | CpuFeatureSet::Ssse3
+            | CpuFeatureSet::Sse41
+            | CpuFeatureSet::Sse42
+            | CpuFeatureSet::Sse4a
+            | CpuFeatureSet::Avx
+            | CpuFeatureSet::Avx2
+            | CpuFeatureSet::Avx512F
+            | CpuFeatureSet::Avx512Cd
+            | CpuFeatureSet::Avx512Pf
+            | CpuFeatureSet::Avx512Er
+            | CpuFeatureSet::Avx512Bw
+            | CpuFeatureSet::Avx512Dq
+            | CpuFeatureSet::Avx512Vl
+            | CpuFeatureSet::Avx512Ifma
+            | CpuFeatureSet::Avx512Vbmi
+            | CpuFeatureSet::Avx512Vpopcntdq
+            | CpuFeatureSet::Avx512Vbmi2
+            | CpuFeatureSet::Avx512Vnni
+            | CpuFeatureSet::Avx512Bitalg
+            | CpuFeatureSet::Avx512Bf16
+            | CpuFeatureSet::Avx512Vp2Intersect
+            | CpuFeatureSet::Avx512Fp16
+            | CpuFeatureSet::AvxVnni
+            | CpuFeatureSet::AvxIfma
+            | CpuFeatureSet::AvxNeConvert
+            | CpuFeatureSet::AvxVnniInt8
+            | CpuFeatureSet::AvxVnniInt16
+            | CpuFeatureSet::Fma
+            | CpuFeatureSet::F16c
+            | CpuFeatureSet::Popcnt
+            | CpuFeatureSet::Lzcnt
+            | CpuFeatureSet::Bmi1
+            | CpuFeatureSet::Bmi2
+            | CpuFeatureSet::Movbe
+            | CpuFeatureSet::Cmpxchg16b
+            | CpuFeatureSet::Sha
+            | CpuFeatureSet::Vaes
+            | CpuFeatureSet::Vpclmulqdq
+            | CpuFeatureSet::Gfni
+            | CpuFeatureSet::AmxTile
+            | CpuFeatureSet::AmxInt8
+            | CpuFeatureSet::AmxBf16
+            | CpuFeatureSet::AmxFp16
+            | CpuFeatureSet::Apx => false,
+
+            CpuFeatureSet::RvM
+            | CpuFeatureSet::RvA
+            | CpuFeatureSet::RvF
+            | CpuFeatureSet::RvD
+            | CpuFeatureSet::RvC
+            | CpuFeatureSet::RvV
+            | CpuFeatureSet::Zba
+            | CpuFeatureSet::Zbb
+            | CpuFeatureSet::Zbc
+            | CpuFeatureSet::Zbs
+            | CpuFeatureSet::Zfh
+            | CpuFeatureSet::Zvfh => false,
+        }
+    }
+
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     fn is_supported_x86(&self) -> bool {
         use std::is_x86_feature_detected;
@@ -111,6 +443,109 @@ impl CpuFeatureSet {
             CpuFeatureSet::AvxNeConvert => is_x86_feature_detected!("avxneconvert"),
             CpuFeatureSet::AvxVnniInt8 => is_x86_feature_detected!("avxvnniint8"),
             CpuFeatureSet::AvxVnniInt16 => is_x86_feature_detected!("avxvnniint16"),
+
+            CpuFeatureSet::Fma => is_x86_feature_detected!("fma"),
+            CpuFeatureSet::F16c => is_x86_feature_detected!("f16c"),
+
+            CpuFeatureSet::Popcnt => is_x86_feature_detected!("popcnt"),
+            CpuFeatureSet::Lzcnt => is_x86_feature_detected!("lzcnt"),
+            CpuFeatureSet::Bmi1 => is_x86_feature_detected!("bmi1"),
+            CpuFeatureSet::Bmi2 => is_x86_feature_detected!("bmi2"),
+            CpuFeatureSet::Movbe => is_x86_feature_detected!("movbe"),
+            CpuFeatureSet::Cmpxchg16b => is_x86_feature_detected!("cmpxchg16b"),
+
+            CpuFeatureSet::Aes => is_x86_feature_detected!("aes"),
+            CpuFeatureSet::Sha => is_x86_feature_detected!("sha"),
+            CpuFeatureSet::Vaes => is_x86_feature_detected!("vaes"),
+            CpuFeatureSet::Vpclmulqdq => is_x86_feature_detected!("vpclmulqdq"),
+            CpuFeatureSet::Gfni => is_x86_feature_detected!("gfni"),
+
+            // AMX and APX detection is not stable in std yet
+            CpuFeatureSet::AmxTile => x86::amx_tile(),
+            CpuFeatureSet::AmxInt8 => x86::amx_int8(),
+            CpuFeatureSet::AmxBf16 => x86::amx_bf16(),
+            CpuFeatureSet::AmxFp16 => x86::amx_fp16(),
+            CpuFeatureSet::Apx => x86::apx(),
+
+            CpuFeatureSet::Neon
+            | CpuFeatureSet::Fp16
+            | CpuFeatureSet::Fhm
+            | CpuFeatureSet::DotProd
+            | CpuFeatureSet::I8mm
+            | CpuFeatureSet::Bf16
+            | CpuFeatureSet::Sve
+            | CpuFeatureSet::Sve2
+            | CpuFeatureSet::Pmull
+            | CpuFeatureSet::Sha2
+            | CpuFeatureSet::Sha3
+            | CpuFeatureSet::Crc32
+            | CpuFeatureSet::Lse => false,
+
+            CpuFeatureSet::RvM
+            | CpuFeatureSet::RvA
+            | CpuFeatureSet::RvF
+            | CpuFeatureSet::RvD
+            | CpuFeatureSet::RvC
+            | CpuFeatureSet::RvV
+            | CpuFeatureSet::Zba
+            | CpuFeatureSet::Zbb
+            | CpuFeatureSet::Zbc
+            | CpuFeatureSet::Zbs
+            | CpuFeatureSet::Zfh
+            | CpuFeatureSet::Zvfh => false,
+        }
+    }
+}
+
+/// x86-64 micro-architecture levels as defined by the x86-64 psABI
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CpuFeatureLevel {
+    #[serde(rename = "x86-64")]
+    X86_64V1,
+    #[serde(rename = "x86-64-v2")]
+    X86_64V2,
+    #[serde(rename = "x86-64-v3")]
+    X86_64V3,
+    #[serde(rename = "x86-64-v4")]
+    X86_64V4,
+}
+
+impl CpuFeatureLevel {
+    /// Highest level fully covered by `features`, `None` for non x86 feature sets.
+    ///
+    /// LAHF/SAHF (required by v2) is not tracked, it is present on every x86-64 cpu
+    /// that also has CMPXCHG16B and SSE4.2.
+    pub fn from_features(features: &[CpuFeatureSet]) -> Option<Self> {
+        use CpuFeatureSet::*;
+
+        let has_all = |required: &[CpuFeatureSet]| required.iter().all(|f| features.contains(f));
+
+        if !has_all(&[Sse, Sse2]) {
+            return None;
+        }
+
+        let v2 = has_all(&[Cmpxchg16b, Popcnt, Sse3, Ssse3, Sse41, Sse42]);
+        let v3 = v2 && has_all(&[Avx, Avx2, Bmi1, Bmi2, F16c, Fma, Lzcnt, Movbe]);
+        let v4 = v3 && has_all(&[Avx512F, Avx512Bw, Avx512Cd, Avx512Dq, Avx512Vl]);
+
+        Some(if v4 {
+            CpuFeatureLevel::X86_64V4
+        } else if v3 {
+            CpuFeatureLevel::X86_64V3
+        } else if v2 {
+            CpuFeatureLevel::X86_64V2
+        } else {
+            CpuFeatureLevel::X86_64V1
+        })
+    }
+
+    /// Name as used by `-C target-cpu`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CpuFeatureLevel::X86_64V1 => "x86-64",
+            CpuFeatureLevel::X86_64V2 => "x86-64-v2",
+            CpuFeatureLevel::X86_64V3 => "x86-64-v3",
+            CpuFeatureLevel::X86_64V4 => "x86-64-v4",
         }
     }
 }
@@ -122,6 +557,18 @@ pub enum CPUVendor {
     Apple,
     Qualcomm,
     Nvidia,
+    // ARM implementers and licensees
+    Arm,
+    Ampere,
+    Amazon,
+    AppliedMicro,
+    Broadcom,
+    Cavium,
+    Fujitsu,
+    HiSilicon,
+    Marvell,
+    Microsoft,
+    Samsung,
     // Virtual Machines
     VirtualPC,
     Rosetta2,
@@ -154,3 +601,133 @@ pub enum CPUArchitecture {
     RiscV64,
     Unknown,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::fixture;
+
+    fn features_of(name: &str) -> Vec<CpuFeatureSet> {
+        CpuFeatureSet::from_cpuinfo(&ProcCpuInfo::parse(&fixture(&format!("cpuinfo/{}", name))))
+    }
+
+    #[test]
+    fn reads_arm_features() {
+        assert_eq!(
+            features_of("aarch64.txt"),
+            vec![
+                CpuFeatureSet::Neon,
+                CpuFeatureSet::Fp16,
+                CpuFeatureSet::Fhm,
+                CpuFeatureSet::DotProd,
+                CpuFeatureSet::I8mm,
+                CpuFeatureSet::Bf16,
+                CpuFeatureSet::Sve,
+                CpuFeatureSet::Aes,
+                CpuFeatureSet::Pmull,
+                CpuFeatureSet::Sha2,
+                CpuFeatureSet::Sha3,
+                CpuFeatureSet::Crc32,
+                CpuFeatureSet::Lse,
+            ]
+        );
+
+        let rk3588 = features_of("rk3588.txt");
+        assert!(rk3588.contains(&CpuFeatureSet::DotProd));
+        assert!(!rk3588.contains(&CpuFeatureSet::Sve));
+        assert!(!rk3588.contains(&CpuFeatureSet::I8mm));
+    }
+
+    #[test]
+    fn reads_riscv_isa() {
+        assert_eq!(
+            features_of("riscv64.txt"),
+            vec![
+                CpuFeatureSet::RvM,
+                CpuFeatureSet::RvA,
+                CpuFeatureSet::RvF,
+                CpuFeatureSet::RvD,
+                CpuFeatureSet::RvC,
+                CpuFeatureSet::RvV,
+                CpuFeatureSet::Zba,
+                CpuFeatureSet::Zbb,
+                CpuFeatureSet::Zbc,
+                CpuFeatureSet::Zbs,
+                CpuFeatureSet::Zfh,
+                CpuFeatureSet::Zvfh,
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_riscv_shorthands() {
+        let cpuinfo = ProcCpuInfo::parse("processor\t: 0\nhart\t\t: 0\nisa\t\t: rv64gczba_zbb\n");
+        assert_eq!(
+            CpuFeatureSet::from_cpuinfo(&cpuinfo),
+            vec![
+                CpuFeatureSet::RvM,
+                CpuFeatureSet::RvA,
+                CpuFeatureSet::RvF,
+                CpuFeatureSet::RvD,
+                CpuFeatureSet::RvC,
+                CpuFeatureSet::Zba,
+                CpuFeatureSet::Zbb,
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_x86_flags() {
+        // x86 features come from runtime detection, not from the flags line
+        assert!(features_of("x86_64.txt").is_empty());
+    }
+
+    #[test]
+    fn classifies_x86_64_levels() {
+        use CpuFeatureSet::*;
+
+        let base = vec![Sse, Sse2];
+        let v2: Vec<CpuFeatureSet> =
+            [&base[..], &[Cmpxchg16b, Popcnt, Sse3, Ssse3, Sse41, Sse42]].concat();
+        let v3 = [&v2[..], &[Avx, Avx2, Bmi1, Bmi2, F16c, Fma, Lzcnt, Movbe]].concat();
+        let v4 = [&v3[..], &[Avx512F, Avx512Bw, Avx512Cd, Avx512Dq, Avx512Vl]].concat();
+        let without = |features: &[CpuFeatureSet], missing: CpuFeatureSet| -> Vec<CpuFeatureSet> {
+            features.iter().copied().filter(|f| *f != missing).collect()
+        };
+        let level = |features: &[CpuFeatureSet]| CpuFeatureLevel::from_features(features);
+
+        let cases = [
+            (base.clone(), Some(CpuFeatureLevel::X86_64V1)),
+            (without(&v2, Popcnt), Some(CpuFeatureLevel::X86_64V1)),
+            (without(&v2, Cmpxchg16b), Some(CpuFeatureLevel::X86_64V1)),
+            (v2.clone(), Some(CpuFeatureLevel::X86_64V2)),
+            // AVX2 alone does not make v3, Haswell also brought FMA, BMI2 and MOVBE
+            (
+                [&v2[..], &[Avx, Avx2]].concat(),
+                Some(CpuFeatureLevel::X86_64V2),
+            ),
+            (without(&v3, Fma), Some(CpuFeatureLevel::X86_64V2)),
+            (without(&v3, Bmi2), Some(CpuFeatureLevel::X86_64V2)),
+            (without(&v3, Movbe), Some(CpuFeatureLevel::X86_64V2)),
+            (v3.clone(), Some(CpuFeatureLevel::X86_64V3)),
+            // Knights Landing has AVX-512 F and CD but not BW, DQ and VL
+            (
+                [&v3[..], &[Avx512F, Avx512Cd]].concat(),
+                Some(CpuFeatureLevel::X86_64V3),
+            ),
+            (without(&v4, Avx512Bw), Some(CpuFeatureLevel::X86_64V3)),
+            (without(&v4, Avx512Dq), Some(CpuFeatureLevel::X86_64V3)),
+            (without(&v4, Avx512Vl), Some(CpuFeatureLevel::X86_64V3)),
+            (v4.clone(), Some(CpuFeatureLevel::X86_64V4)),
+            // the levels build on each other, AVX-512 without v2 is still v1
+            (without(&v4, Sse42), Some(CpuFeatureLevel::X86_64V1)),
+            (vec![Neon, Aes], None),
+            (Vec::new(), None),
+        ];
+
+        for (features, expected) in cases {
+            assert_eq!(level(&features), expected, "{:?}", features);
+        }
+        assert_eq!(CpuFeatureLevel::X86_64V4.as_str(), "x86-64-v4");
+    }
+}
@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use super::CpuFrequency;
+use crate::sysfs;
+
+/// Thermal throttle event counters of a logical cpu (Intel only)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThrottleCounters {
+    pub cpu: u32,
+    pub core_throttle_count: Option<u64>,
+    /// in milliseconds
+    pub core_throttle_time: Option<u64>,
+    pub package_throttle_count: Option<u64>,
+    /// in milliseconds
+    pub package_throttle_time: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PowerConstraint {
+    /// e.g. `long_term`, `short_term`, `peak_power`
+    pub name: Option<String>,
+    /// in microwatts
+    pub power_limit: u64,
+    /// in microseconds
+    pub time_window: Option<u64>,
+    /// in microwatts
+    pub max_power: Option<u64>,
+}
+
+/// A RAPL power capping zone such as `package-0`, `core`, `uncore`, `dram` or `psys`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PowerZone {
+    /// powercap id, e.g. `intel-rapl:0:1`
+    pub id: String,
+    pub name: String,
+    pub enabled: Option<bool>,
+    /// Cumulative energy in microjoules, usually only readable by root
+    pub energy: Option<u64>,
+    /// Value at which `energy` wraps around, in microjoules
+    pub max_energy_range: Option<u64>,
+    pub constraints: Vec<PowerConstraint>,
+}
+
+impl PowerZone {
+    /// Average power in watts between an earlier reading of the same zone and this one
+    pub fn average_power(&self, earlier: &PowerZone, elapsed: Duration) -> Option<f64> {
+        let (now, before) = (self.energy?, earlier.energy?);
+        if elapsed.is_zero() {
+            return None;
+        }
+
+        let consumed = if now >= before {
+            now - before
+        } else {
+            // counter wrapped
+            self.max_energy_range?.checked_sub(before)? + now
+        };
+
+        Some(consumed as f64 / 1_000_000.0 / elapsed.as_secs_f64())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuThrottleInfo {
+    pub throttle_counters: Vec<ThrottleCounters>,
+    pub power_zones: Vec<PowerZone>,
+    /// Lowest ratio of the cpufreq policy limit (scaling_max) to the hardware max across all
+    /// cpus, `None` without cpufreq data. The limit is set by the user, a power profile daemon
+    /// or the firmware (`_PPC`); it says nothing about the frequency the cpu runs at right now.
+    pub policy_cap_ratio: Option<f32>,
+    /// true when the policy of at least one cpu forbids its hardware max frequency. Thermal and
+    /// power limit throttling show up in `throttle_counters` and `power_zones` instead.
+    pub policy_capped: bool,
+}
+
+impl CpuThrottleInfo {
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Self {
+        let root = root.as_ref();
+
+        let policy_cap_ratio = CpuFrequency::read_all(root)
+            .iter()
+            .filter_map(|f| match (f.scaling_max, f.max) {
+                (Some(limit), Some(max)) if max > 0 => Some(limit as f32 / max as f32),
+                _ => None,
+            })
+            .reduce(f32::min);
+
+        CpuThrottleInfo {
+            throttle_counters: Self::read_throttle_counters(root),
+            power_zones: Self::read_power_zones(root),
+            policy_cap_ratio,
+            policy_capped: policy_cap_ratio.map(|r| r < 1.0).unwrap_or(false),
+        }
+    }
+
+    fn read_throttle_counters(root: &Path) -> Vec<ThrottleCounters> {
+        sysfs::list_cpus(root)
+            .into_iter()
+            .filter_map(|cpu| {
+                let dir = root.join(format!(
+                    "sys/devices/system/cpu/cpu{}/thermal_throttle",
+                    cpu
+                ));
+                if !dir.is_dir() {
+                    return None;
+                }
+
+                Some(ThrottleCounters {
+                    cpu,
+                    core_throttle_count: sysfs::read_u64(dir.join("core_throttle_count")),
+                    core_throttle_time: sysfs::read_u64(dir.join("core_throttle_total_time_ms")),
+                    package_throttle_count: sysfs::read_u64(dir.join("package_throttle_count")),
+                    package_throttle_time: sysfs::read_u64(
+                        dir.join("package_throttle_total_time_ms"),
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// Zones under `<root>/sys/class/powercap`, AMD cpus are exposed through the same
+    /// intel-rapl interface
+    fn read_power_zones(root: &Path) -> Vec<PowerZone> {
+        let entries = match std::fs::read_dir(root.join("sys/class/powercap")) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut zones: Vec<PowerZone> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let id = entry.file_name().to_string_lossy().to_string();
+                // `intel-rapl` itself is the control type, zones are `intel-rapl:N[:M]`
+                if !id.starts_with("intel-rapl:") {
+                    return None;
+                }
+
+                let dir = entry.path();
+                let name = sysfs::read_string(dir.join("name"))?;
+
+                let constraints = (0..8)
+                    .map_while(|index| {
+                        let file =
+                            |suffix: &str| dir.join(format!("constraint_{}_{}", index, suffix));
+                        Some(PowerConstraint {
+                            name: sysfs::read_string(file("name")),
+                            power_limit: sysfs::read_u64(file("power_limit_uw"))?,
+                            time_window: sysfs::read_u64(file("time_window_us")),
+                            max_power: sysfs::read_u64(file("max_power_uw")),
+                        })
+                    })
+                    .collect();
+
+                Some(PowerZone {
+                    id,
+                    name,
+                    enabled: sysfs::read_bool(dir.join("enabled")),
+                    energy: sysfs::read_u64(dir.join("energy_uj")),
+                    max_energy_range: sysfs::read_u64(dir.join("max_energy_range_uj")),
+                    constraints,
+                })
+            })
+            .collect();
+
+        zones.sort_by(|a, b| a.id.cmp(&b.id));
+        zones
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    fn add_policy(root: &FakeRoot, cpu: u32, scaling_max: u32, max: u32) {
+        let dir = format!("sys/devices/system/cpu/cpu{}/cpufreq", cpu);
+        root.write(
+            &format!("{}/scaling_max_freq", dir),
+            &scaling_max.to_string(),
+        )
+        .write(&format!("{}/cpuinfo_max_freq", dir), &max.to_string())
+        .write(&format!("{}/scaling_cur_freq", dir), "800000");
+    }
+
+    #[test]
+    fn reports_policy_caps() {
+        let root = FakeRoot::new("power-policy");
+        add_policy(&root, 0, 4_700_000, 4_700_000);
+        add_policy(&root, 1, 2_350_000, 4_700_000);
+
+        let info = CpuThrottleInfo::from_root(root.path());
+        assert_eq!(info.policy_cap_ratio, Some(0.5));
+        assert!(info.policy_capped);
+    }
+
+    #[test]
+    fn idle_cpus_are_not_capped() {
+        // a low current frequency alone is not a cap
+        let root = FakeRoot::new("power-idle");
+        add_policy(&root, 0, 4_700_000, 4_700_000);
+
+        let info = CpuThrottleInfo::from_root(root.path());
+        assert_eq!(info.policy_cap_ratio, Some(1.0));
+        assert!(!info.policy_capped);
+    }
+
+    #[test]
+    fn averages_wrapping_energy_counters() {
+        let zone = |energy| PowerZone {
+            energy: Some(energy),
+            max_energy_range: Some(262_143_328_850),
+            ..Default::default()
+        };
+
+        let power = zone(1_000_000).average_power(&zone(262_142_328_850), Duration::from_secs(1));
+        assert_eq!(power.map(|w| w.round()), Some(2.0));
+    }
+}
@@ -0,0 +1,308 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Cumulative time counters of a cpu line in /proc/stat, in USER_HZ ticks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+}
+
+impl CpuTimes {
+    /// guest and guest_nice are already accounted in user and nice, so they are not part of the total
+    pub fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+
+    fn from_fields(fields: &[&str]) -> Self {
+        let field = |i: usize| {
+            fields
+                .get(i)
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+
+        CpuTimes {
+            user: field(0),
+            nice: field(1),
+            system: field(2),
+            idle: field(3),
+            iowait: field(4),
+            irq: field(5),
+            softirq: field(6),
+            steal: field(7),
+        }
+    }
+}
+
+/// Snapshot of the cpu lines of /proc/stat
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcStat {
+    pub aggregate: CpuTimes,
+    /// (logical cpu index, counters)
+    pub per_cpu: Vec<(u32, CpuTimes)>,
+}
+
+impl ProcStat {
+    /// Reads and parses `<root>/proc/stat`, pass `/` for the running system
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(root.as_ref().join("proc/stat"))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut result = ProcStat::default();
+
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let label = match parts.next() {
+                Some(label) if label.starts_with("cpu") => label,
+                _ => continue,
+            };
+            let fields: Vec<&str> = parts.collect();
+            let times = CpuTimes::from_fields(&fields);
+
+            match label.trim_start_matches("cpu") {
+                "" => result.aggregate = times,
+                index => {
+                    if let Ok(index) = index.parse::<u32>() {
+                        result.per_cpu.push((index, times));
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Computes the utilization between an earlier snapshot and this one.
+    /// CPUs that are missing from either snapshot (e.g. hot-unplugged) are skipped.
+    pub fn usage_since(&self, previous: &ProcStat) -> CpuUsageSample {
+        let per_cpu = self
+            .per_cpu
+            .iter()
+            .filter_map(|(index, current)| {
+                previous
+                    .per_cpu
+                    .iter()
+                    .find(|(prev_index, _)| prev_index == index)
+                    .map(|(_, prev)| CpuUtilization::between(Some(*index), prev, current))
+            })
+            .collect();
+
+        CpuUsageSample {
+            aggregate: CpuUtilization::between(None, &previous.aggregate, &self.aggregate),
+            per_cpu,
+        }
+    }
+}
+
+/// Share of time spent in each state over a sampling interval, in percent
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CpuUtilization {
+    /// Logical cpu index, `None` for the aggregate of all cpus
+    pub cpu: Option<u32>,
+    /// Everything except idle and iowait
+    pub busy: f32,
+    pub user: f32,
+    pub nice: f32,
+    pub system: f32,
+    pub idle: f32,
+    pub iowait: f32,
+    pub irq: f32,
+    pub softirq: f32,
+    pub steal: f32,
+}
+
+impl CpuUtilization {
+    fn between(cpu: Option<u32>, previous: &CpuTimes, current: &CpuTimes) -> Self {
+        let total = current.total().saturating_sub(previous.total());
+        if total == 0 {
+            return CpuUtilization {
+                cpu,
+                ..Default::default()
+            };
+        }
+
+        let percent =
+            |prev: u64, cur: u64| (cur.saturating_sub(prev) as f64 * 100.0 / total as f64) as f32;
+
+        let idle = percent(previous.idle, current.idle);
+        let iowait = percent(previous.iowait, current.iowait);
+
+        CpuUtilization {
+            cpu,
+            busy: (100.0 - idle - iowait).max(0.0),
+            user: percent(previous.user, current.user),
+            nice: percent(previous.nice, current.nice),
+            system: percent(previous.system, current.system),
+            idle,
+            iowait,
+            irq: percent(previous.irq, current.irq),
+            softirq: percent(previous.softirq, current.softirq),
+            steal: percent(previous.steal, current.steal),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuUsageSample {
+    pub aggregate: CpuUtilization,
+    pub per_cpu: Vec<CpuUtilization>,
+}
+
+/// Measures cpu utilization from the difference between two /proc/stat snapshots.
+///
+/// The first snapshot is taken on construction, every call to `sample` reports the
+/// utilization since the previous call.
+pub struct CpuSampler {
+    root: PathBuf,
+    previous: ProcStat,
+}
+
+impl CpuSampler {
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_root("/")
+    }
+
+    pub fn with_root<P: AsRef<Path>>(root: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let root = root.as_ref().to_path_buf();
+        let previous = ProcStat::from_root(&root)?;
+        Ok(Self { root, previous })
+    }
+
+    pub fn sample(&mut self) -> Result<CpuUsageSample, Box<dyn std::error::Error>> {
+        let current = ProcStat::from_root(&self.root)?;
+        let usage = current.usage_since(&self.previous);
+        self.previous = current;
+        Ok(usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, FakeRoot};
+
+    fn times(user: u64, system: u64, idle: u64, iowait: u64, steal: u64) -> CpuTimes {
+        CpuTimes {
+            user,
+            system,
+            idle,
+            iowait,
+            steal,
+            ..Default::default()
+        }
+    }
+
+    fn snapshot(aggregate: CpuTimes, per_cpu: &[(u32, CpuTimes)]) -> ProcStat {
+        ProcStat {
+            aggregate,
+            per_cpu: per_cpu.to_vec(),
+        }
+    }
+
+    #[test]
+    fn parses_cpu_lines() {
+        let stat = ProcStat::parse(&fixture("stat/linux-6.8.txt"));
+
+        assert_eq!(
+            stat.aggregate,
+            CpuTimes {
+                user: 4705,
+                nice: 356,
+                system: 584,
+                idle: 3699176,
+                iowait: 2323,
+                irq: 0,
+                softirq: 245,
+                steal: 120,
+            }
+        );
+        // guest (300) and guest_nice (10) are part of user and nice already
+        assert_eq!(
+            stat.aggregate.total(),
+            4705 + 356 + 584 + 3699176 + 2323 + 245 + 120
+        );
+        let indices: Vec<u32> = stat.per_cpu.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, vec![0, 1]);
+        assert_eq!(stat.per_cpu[1].1.iowait, 1694);
+    }
+
+    #[test]
+    fn defaults_missing_columns_to_zero() {
+        // kernels before 2.6.11 have no steal column
+        let stat = ProcStat::parse("cpu  10 0 5 100 2 0 1\n");
+        assert_eq!(stat.aggregate.steal, 0);
+        assert_eq!(stat.aggregate.total(), 118);
+    }
+
+    #[test]
+    fn computes_utilization() {
+        let previous = snapshot(
+            times(100, 50, 800, 40, 10),
+            &[(0, times(100, 50, 800, 40, 10))],
+        );
+        let current = snapshot(
+            times(130, 60, 850, 50, 10),
+            &[(0, times(130, 60, 850, 50, 10)), (1, times(1, 1, 1, 1, 1))],
+        );
+
+        let usage = current.usage_since(&previous);
+        let aggregate = usage.aggregate;
+        assert_eq!(aggregate.cpu, None);
+        assert_eq!(aggregate.user, 30.0);
+        assert_eq!(aggregate.system, 10.0);
+        assert_eq!(aggregate.idle, 50.0);
+        assert_eq!(aggregate.iowait, 10.0);
+        // iowait is idle time waiting on io, not busy time
+        assert_eq!(aggregate.busy, 40.0);
+        // cpu1 came online between the snapshots
+        assert_eq!(usage.per_cpu.len(), 1);
+        assert_eq!(usage.per_cpu[0].cpu, Some(0));
+    }
+
+    #[test]
+    fn ignores_counters_that_go_backwards() {
+        // iowait is known to decrease on some kernels
+        let previous = snapshot(times(100, 0, 800, 50, 0), &[]);
+        let current = snapshot(times(200, 0, 900, 40, 0), &[]);
+
+        let usage = current.usage_since(&previous).aggregate;
+        assert_eq!(usage.iowait, 0.0);
+        // the 10 lost iowait ticks shrink the total to 190
+        assert!((usage.user - 100.0 * 100.0 / 190.0).abs() < 1e-3);
+        assert!(usage.busy >= 0.0 && usage.busy <= 100.0);
+    }
+
+    #[test]
+    fn reports_zero_without_elapsed_ticks() {
+        let stat = snapshot(times(100, 50, 800, 40, 10), &[]);
+
+        let usage = stat.usage_since(&stat).aggregate;
+        assert_eq!((usage.busy, usage.idle), (0.0, 0.0));
+    }
+
+    #[test]
+    fn samples_from_root() {
+        let root = FakeRoot::new("proc-stat");
+        root.write("proc/stat", "cpu  100 0 0 900 0 0 0 0\n");
+        let mut sampler = CpuSampler::with_root(root.path()).unwrap();
+
+        root.write("proc/stat", "cpu  150 0 0 950 0 0 0 0\n");
+        assert_eq!(sampler.sample().unwrap().aggregate.busy, 50.0);
+    }
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::sysfs;
+
+/// Physical layout of the logical cpus: packages > dies > clusters > cores > SMT threads
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuTopology {
+    pub packages: Vec<CpuPackage>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuPackage {
+    pub id: u32,
+    pub dies: Vec<CpuDie>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuDie {
+    pub id: u32,
+    pub clusters: Vec<CpuCluster>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuCluster {
+    pub id: u32,
+    pub cores: Vec<CpuCore>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuCore {
+    pub id: u32,
+    /// Logical cpus running on this core, more than one with SMT
+    pub cpus: Vec<u32>,
+}
+
+type CoreMap = BTreeMap<u32, Vec<u32>>;
+type ClusterMap = BTreeMap<u32, CoreMap>;
+type DieMap = BTreeMap<u32, ClusterMap>;
+
+impl CpuTopology {
+    /// Builds the topology from `<root>/sys/devices/system/cpu/cpuN/topology`.
+    ///
+    /// Only online cpus are included, offline ones have no `topology` directory. Missing
+    /// package, die and cluster ids default to 0.
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Self {
+        let root = root.as_ref();
+        let cpus = sysfs::online_cpus(root);
+        let mut tree: BTreeMap<u32, DieMap> = BTreeMap::new();
+
+        for &cpu in &cpus {
+            let dir = root.join(format!("sys/devices/system/cpu/cpu{}/topology", cpu));
+            let id = |file: &str| sysfs::read_u64(dir.join(file)).map(|v| v as u32);
+            let Some(core_id) = id("core_id") else {
+                continue;
+            };
+
+            let package = id("physical_package_id").unwrap_or(0);
+            let die = id("die_id").unwrap_or(0);
+            let cluster = id("cluster_id").unwrap_or(0);
+
+            let core = tree
+                .entry(package)
+                .or_default()
+                .entry(die)
+                .or_default()
+                .entry(cluster)
+                .or_default();
+
+            let siblings = sysfs::read_string(dir.join("thread_siblings_list"))
+                .map(|list| sysfs::parse_cpu_list(&list))
+                .unwrap_or_default();
+
+            let threads = core.entry(core_id).or_default();
+            // siblings include offline threads of the core, which have no topology to report
+            for thread in siblings.into_iter().chain(std::iter::once(cpu)) {
+                if cpus.contains(&thread) && !threads.contains(&thread) {
+                    threads.push(thread);
+                }
+            }
+            threads.sort_unstable();
+        }
+
+        let packages = tree
+            .into_iter()
+            .map(|(id, dies)| CpuPackage {
+                id,
+                dies: dies
+                    .into_iter()
+                    .map(|(id, clusters)| CpuDie {
+                        id,
+                        clusters: clusters
+                            .into_iter()
+                            .map(|(id, cores)| CpuCluster {
+                                id,
+                                cores: cores
+                                    .into_iter()
+                                    .map(|(id, cpus)| CpuCore { id, cpus })
+                                    .collect(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        CpuTopology { packages }
+    }
+
+    pub fn cores(&self) -> impl Iterator<Item = &CpuCore> {
+        self.packages
+            .iter()
+            .flat_map(|package| package.dies.iter())
+            .flat_map(|die| die.clusters.iter())
+            .flat_map(|cluster| cluster.cores.iter())
+    }
+
+    pub fn num_of_packages(&self) -> u32 {
+        self.packages.len() as u32
+    }
+
+    pub fn num_of_cores(&self) -> u32 {
+        self.cores().count() as u32
+    }
+
+    pub fn logical_cpus(&self) -> Vec<u32> {
+        let mut cpus: Vec<u32> = self.cores().flat_map(|core| core.cpus.clone()).collect();
+        cpus.sort_unstable();
+        cpus
+    }
+
+    /// First logical cpu of every physical core, useful to pin one thread per core
+    pub fn one_cpu_per_core(&self) -> Vec<u32> {
+        let mut cpus: Vec<u32> = self
+            .cores()
+            .filter_map(|core| core.cpus.first().copied())
+            .collect();
+        cpus.sort_unstable();
+        cpus
+    }
+
+    pub fn cpus_in_package(&self, package_id: u32) -> Vec<u32> {
+        let mut cpus: Vec<u32> = self
+            .packages
+            .iter()
+            .filter(|package| package.id == package_id)
+            .flat_map(|package| package.dies.iter())
+            .flat_map(|die| die.clusters.iter())
+            .flat_map(|cluster| cluster.cores.iter())
+            .flat_map(|core| core.cpus.clone())
+            .collect();
+        cpus.sort_unstable();
+        cpus
+    }
+
+    /// Number of physical cores that have at least one of the given logical cpus
+    pub fn count_cores_of(&self, cpus: &[u32]) -> u32 {
+        self.cores()
+            .filter(|core| core.cpus.iter().any(|cpu| cpus.contains(cpu)))
+            .count() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    fn add_cpu(root: &FakeRoot, cpu: u32, package: u32, core: u32, siblings: &str) {
+        let dir = format!("sys/devices/system/cpu/cpu{}/topology", cpu);
+        root.write(
+            &format!("{}/physical_package_id", dir),
+            &package.to_string(),
+        )
+        .write(&format!("{}/core_id", dir), &core.to_string())
+        .write(&format!("{}/thread_siblings_list", dir), siblings);
+    }
+
+    #[test]
+    fn groups_smt_threads() {
+        let root = FakeRoot::new("topology-smt");
+        root.write("sys/devices/system/cpu/online", "0-3\n");
+        add_cpu(&root, 0, 0, 0, "0,2");
+        add_cpu(&root, 1, 0, 1, "1,3");
+        add_cpu(&root, 2, 0, 0, "0,2");
+        add_cpu(&root, 3, 0, 1, "1,3");
+
+        let topology = CpuTopology::from_root(root.path());
+        assert_eq!(topology.num_of_packages(), 1);
+        assert_eq!(topology.num_of_cores(), 2);
+        assert_eq!(topology.one_cpu_per_core(), vec![0, 1]);
+        assert_eq!(topology.logical_cpus(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn skips_offline_cpus() {
+        // cpu3 was offlined: it keeps its cpuN dir but loses topology/, and still shows up in
+        // the siblings of cpu1
+        let root = FakeRoot::new("topology-offline");
+        root.write("sys/devices/system/cpu/online", "0-2\n")
+            .mkdir("sys/devices/system/cpu/cpu3");
+        add_cpu(&root, 0, 0, 0, "0,2");
+        add_cpu(&root, 1, 1, 3, "1,3");
+        add_cpu(&root, 2, 0, 0, "0,2");
+
+        let topology = CpuTopology::from_root(root.path());
+        assert_eq!(topology.num_of_packages(), 2);
+        assert_eq!(topology.num_of_cores(), 2);
+        assert_eq!(topology.cpus_in_package(0), vec![0, 2]);
+        assert_eq!(topology.cpus_in_package(1), vec![1]);
+        assert_eq!(topology.logical_cpus(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn skips_cpus_without_topology() {
+        let root = FakeRoot::new("topology-missing");
+        root.mkdir("sys/devices/system/cpu/cpu1");
+        add_cpu(&root, 0, 0, 1, "0");
+
+        let topology = CpuTopology::from_root(root.path());
+        assert_eq!(topology.num_of_cores(), 1);
+        assert_eq!(topology.logical_cpus(), vec![0]);
+    }
+}
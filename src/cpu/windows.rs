@@ -1,10 +1,10 @@
-use crate::cpu::{CPUData, CPUUsage, CpuFeatureSet};
+use crate::cpu::{CPUData, CPUUsage, CpuFeatureSet, CpuLimits, CpuThrottleInfo, Hypervisor};
 use anyhow::Result;
 use sysinfo::{CpuRefreshKind, RefreshKind, System};
 use winapi::um::sysinfoapi::GetSystemInfo;
 use winapi::um::sysinfoapi::SYSTEM_INFO;
 
-use super::{CPUArchitecture, CPUVendor};
+use super::{CPUArchitecture, CPUVendor, CpuTopology, LoadAverage};
 
 impl CPUUsage {
     pub fn get_cpu_info() -> Result<CPUData, String> {
@@ -14,11 +14,35 @@ impl CPUUsage {
             architecture: Self::get_cpu_architecture(),
             num_of_cores: Self::num_of_cores(),
             logical_processors: Self::logical_processors(),
-            average_cpu_usage: Self::average_usage(),
+            effective_cpus: Self::effective_cpus(),
+            load_average: Self::load_average(),
             instruction_sets: Self::get_instruction_sets(),
+            family: None,
+            model: None,
+            stepping: None,
+            microcode: None,
+            flags: Vec::new(),
+            processors: Vec::new(),
+            frequencies: Vec::new(),
+            topology: CpuTopology::default(),
+            caches: Vec::new(),
+            microarchitecture: None,
+            hypervisor: Hypervisor::detect(),
         })
     }
 
+    pub fn get_cpu_limits() -> Result<CpuLimits, Box<dyn std::error::Error>> {
+        Err("Cpu limits are not implemented for Windows".into())
+    }
+
+    pub fn effective_cpus() -> u32 {
+        Self::logical_processors()
+    }
+
+    pub fn get_throttle_info() -> Result<CpuThrottleInfo, Box<dyn std::error::Error>> {
+        Err("Throttle info is not implemented for Windows".into())
+    }
+
     pub fn num_of_cores() -> u32 {
         sysinfo::System::physical_core_count().unwrap_or(0) as u32
     }
@@ -29,8 +53,8 @@ impl CPUUsage {
         sys.cpus().len() as u32
     }
 
-    pub fn average_usage() -> f32 {
-        sys_info::loadavg().unwrap().one as f32
+    pub fn load_average() -> LoadAverage {
+        LoadAverage::current()
     }
 
     fn get_cpu_name() -> String {
@@ -71,17 +95,7 @@ impl CPUUsage {
     }
 
     fn get_instruction_sets() -> Vec<CpuFeatureSet> {
-        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-        {
-            use strum::IntoEnumIterator;
-            CpuFeatureSet::iter()
-                .filter(|f| f.is_supported_x86())
-                .collect()
-        }
-
-        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
-        {
-            Vec::new()
-        }
+        use strum::IntoEnumIterator;
+        CpuFeatureSet::iter().filter(|f| f.is_supported()).collect()
     }
 }
@@ -0,0 +1,62 @@
+// Raw CPUID access for features that `is_x86_feature_detected!` does not expose on stable
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::{__cpuid, __cpuid_count, _xgetbv, CpuidResult};
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::{__cpuid, __cpuid_count, _xgetbv, CpuidResult};
+
+/// XCR0 state components that have to be enabled by the OS
+const XCR0_TILECFG: u64 = 1 << 17;
+const XCR0_TILEDATA: u64 = 1 << 18;
+const XCR0_APX: u64 = 1 << 19;
+
+// older toolchains still declare the cpuid intrinsics as unsafe
+#[allow(unused_unsafe)]
+pub(crate) fn cpuid(leaf: u32, sub_leaf: u32) -> Option<CpuidResult> {
+    let max_leaf = unsafe { __cpuid(leaf & 0xf000_0000) }.eax;
+    if leaf > max_leaf {
+        return None;
+    }
+
+    Some(unsafe { __cpuid_count(leaf, sub_leaf) })
+}
+
+fn xcr0() -> u64 {
+    #[target_feature(enable = "xsave")]
+    unsafe fn read() -> u64 {
+        _xgetbv(0)
+    }
+
+    // xgetbv faults unless the OS enabled it, which is reported by OSXSAVE
+    let osxsave = cpuid(1, 0).map(|r| r.ecx & (1 << 27) != 0).unwrap_or(false);
+    if osxsave {
+        unsafe { read() }
+    } else {
+        0
+    }
+}
+
+fn amx_enabled() -> bool {
+    let mask = XCR0_TILECFG | XCR0_TILEDATA;
+    xcr0() & mask == mask
+}
+
+pub(crate) fn amx_tile() -> bool {
+    amx_enabled() && cpuid(7, 0).map(|r| r.edx & (1 << 24) != 0).unwrap_or(false)
+}
+
+pub(crate) fn amx_int8() -> bool {
+    amx_enabled() && cpuid(7, 0).map(|r| r.edx & (1 << 25) != 0).unwrap_or(false)
+}
+
+pub(crate) fn amx_bf16() -> bool {
+    amx_enabled() && cpuid(7, 0).map(|r| r.edx & (1 << 22) != 0).unwrap_or(false)
+}
+
+pub(crate) fn amx_fp16() -> bool {
+    amx_enabled() && cpuid(7, 1).map(|r| r.eax & (1 << 21) != 0).unwrap_or(false)
+}
+
+pub(crate) fn apx() -> bool {
+    xcr0() & XCR0_APX != 0 && cpuid(7, 1).map(|r| r.edx & (1 << 21) != 0).unwrap_or(false)
+}
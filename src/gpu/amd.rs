@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::gpu::drm;
+use crate::gpu::PciGpu;
+use crate::sysfs;
+
+/// A power state of `pp_dpm_sclk` / `pp_dpm_mclk`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DpmLevel {
+    pub index: u32,
+    /// in MHz
+    pub frequency: u32,
+    /// The level the gpu currently runs at
+    pub active: bool,
+}
+
+/// Details of a gpu driven by amdgpu, sizes are in bytes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AmdGpu {
+    pub bus_id: String,
+    pub adapter_index: u32,
+    /// Marketing name from the board FRU, only present on some boards
+    pub product_name: Option<String>,
+    /// The gpu is part of the cpu package and its VRAM is a carve-out of system memory
+    pub is_apu: bool,
+    pub vram_total: u64,
+    pub vram_used: u64,
+    /// Part of the VRAM the cpu can map directly, all of it with resizable BAR
+    pub vis_vram_total: u64,
+    pub vis_vram_used: u64,
+    /// System memory mapped through the GPU's page tables
+    pub gtt_total: u64,
+    pub gtt_used: u64,
+    pub gpu_busy_percent: Option<u32>,
+    pub mem_busy_percent: Option<u32>,
+    /// Shader clock levels
+    pub sclk: Vec<DpmLevel>,
+    /// Memory clock levels
+    pub mclk: Vec<DpmLevel>,
+    /// Average or instantaneous board power in watts
+    pub power: Option<f32>,
+    /// Power limit in watts
+    pub power_cap: Option<f32>,
+    /// in degrees Celsius
+    pub edge_temperature: Option<f32>,
+    pub junction_temperature: Option<f32>,
+    pub memory_temperature: Option<f32>,
+}
+
+impl AmdGpu {
+    /// All amdgpu devices under `root`, `adapter_index` matches `PciGpu::read_adapters`
+    pub fn read_all<P: AsRef<Path>>(root: P) -> Vec<Self> {
+        let root = root.as_ref();
+        PciGpu::read_all(root)
+            .iter()
+            .enumerate()
+            .filter_map(|(index, gpu)| {
+                let mut amd = Self::from_device(root, gpu)?;
+                amd.adapter_index = index as u32;
+                Some(amd)
+            })
+            .collect()
+    }
+
+    /// `None` unless the device is bound to amdgpu
+    pub fn from_device<P: AsRef<Path>>(root: P, gpu: &PciGpu) -> Option<Self> {
+        if gpu.driver.as_deref() != Some("amdgpu") {
+            return None;
+        }
+
+        let root = root.as_ref();
+        let dir = gpu.sysfs_path(root);
+        let size = |file: &str| sysfs::read_u64(dir.join(file)).unwrap_or(0);
+        let percent = |file: &str| sysfs::read_u64(dir.join(file)).map(|v| v as u32);
+        let levels = |file: &str| {
+            sysfs::read_string(dir.join(file))
+                .map(|content| Self::parse_dpm_levels(&content))
+                .unwrap_or_default()
+        };
+
+        let mut amd = AmdGpu {
+            bus_id: gpu.bus_id.clone(),
+            adapter_index: 0,
+            product_name: sysfs::read_string(dir.join("product_name")),
+            vram_total: size("mem_info_vram_total"),
+            vram_used: size("mem_info_vram_used"),
+            vis_vram_total: size("mem_info_vis_vram_total"),
+            vis_vram_used: size("mem_info_vis_vram_used"),
+            gtt_total: size("mem_info_gtt_total"),
+            gtt_used: size("mem_info_gtt_used"),
+            gpu_busy_percent: percent("gpu_busy_percent"),
+            mem_busy_percent: percent("mem_busy_percent"),
+            sclk: levels("pp_dpm_sclk"),
+            mclk: levels("pp_dpm_mclk"),
+            ..Default::default()
+        };
+        amd.is_apu = drm::render_node(root, &dir)
+            .and_then(|node| drm::amdgpu_ids_flags(&node))
+            .map(|flags| flags & drm::AMDGPU_IDS_FLAGS_FUSION != 0)
+            .unwrap_or_else(|| amd.looks_like_apu());
+
+        if let Some((_, hwmon)) = sysfs::indexed_entries(dir.join("hwmon"), "hwmon")
+            .into_iter()
+            .next()
+        {
+            let watts = |file: &str| sysfs::read_u64(hwmon.join(file)).map(|uw| uw as f32 / 1e6);
+            // newer kernels report the instantaneous power of RDNA3+ in power1_input only
+            amd.power = watts("power1_average").or_else(|| watts("power1_input"));
+            amd.power_cap = watts("power1_cap");
+
+            for index in 1..=3 {
+                let celsius = sysfs::read_string(hwmon.join(format!("temp{}_input", index)))
+                    .and_then(|v| v.parse::<f32>().ok())
+                    .map(|v| v / 1000.0);
+                match sysfs::read_string(hwmon.join(format!("temp{}_label", index))).as_deref() {
+                    Some("edge") => amd.edge_temperature = celsius,
+                    Some("junction") => amd.junction_temperature = celsius,
+                    Some("mem") => amd.memory_temperature = celsius,
+                    _ => {}
+                }
+            }
+        }
+
+        Some(amd)
+    }
+
+    /// Parses `0: 500Mhz` lines, the active level is marked with `*`
+    pub fn parse_dpm_levels(content: &str) -> Vec<DpmLevel> {
+        content
+            .lines()
+            .filter_map(|line| {
+                let (index, rest) = line.split_once(':')?;
+                let frequency = rest.split_whitespace().next()?;
+                let frequency = frequency
+                    .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                    .parse::<u32>()
+                    .ok()?;
+
+                Some(DpmLevel {
+                    // deep sleep states are listed as `S: 19Mhz`
+                    index: index.trim().parse().ok()?,
+                    frequency,
+                    active: rest.trim_end().ends_with('*'),
+                })
+            })
+            .collect()
+    }
+
+    /// Current shader clock in MHz
+    pub fn current_sclk(&self) -> Option<u32> {
+        self.sclk.iter().find(|l| l.active).map(|l| l.frequency)
+    }
+
+    /// Current memory clock in MHz
+    pub fn current_mclk(&self) -> Option<u32> {
+        self.mclk.iter().find(|l| l.active).map(|l| l.frequency)
+    }
+
+    /// Guess for when the render node cannot be queried: APUs usually have a small carve-out
+    /// the cpu can map entirely, discrete cards have at least 4 GB or, without resizable BAR,
+    /// a small visible window. Misses APUs with a larger carve-out set in the firmware.
+    fn looks_like_apu(&self) -> bool {
+        self.vram_total > 0
+            && self.vram_total <= 2 * 1024 * 1024 * 1024
+            && self.vis_vram_total == self.vram_total
+    }
+
+    /// VRAM, plus GTT for APUs where both come from system memory
+    pub fn total_memory(&self) -> u64 {
+        if self.is_apu {
+            self.vram_total + self.gtt_total
+        } else {
+            self.vram_total
+        }
+    }
+
+    pub fn used_memory(&self) -> u64 {
+        if self.is_apu {
+            self.vram_used + self.gtt_used
+        } else {
+            self.vram_used
+        }
+    }
+}
+
+// the fake trees need `:` in file names
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn amdgpu(bus_id: &str) -> PciGpu {
+        PciGpu {
+            bus_id: bus_id.to_string(),
+            vendor_id: 0x1002,
+            driver: Some("amdgpu".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn write_memory(root: &FakeRoot, bus_id: &str, vram: u64, vis_vram: u64) {
+        let dir = format!("sys/bus/pci/devices/{}", bus_id);
+        root.write(&format!("{}/mem_info_vram_total", dir), &vram.to_string())
+            .write(
+                &format!("{}/mem_info_vis_vram_total", dir),
+                &vis_vram.to_string(),
+            )
+            .write(&format!("{}/mem_info_vram_used", dir), "1048576\n")
+            .write(
+                &format!("{}/mem_info_gtt_total", dir),
+                &(8192 * MIB).to_string(),
+            )
+            .write(&format!("{}/mem_info_gtt_used", dir), "2097152\n");
+    }
+
+    #[test]
+    fn parses_dpm_levels() {
+        let levels = AmdGpu::parse_dpm_levels("S: 19Mhz\n0: 500Mhz\n1: 1200Mhz *\n2: 2500Mhz\n");
+
+        let summary: Vec<(u32, u32, bool)> = levels
+            .iter()
+            .map(|l| (l.index, l.frequency, l.active))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(0, 500, false), (1, 1200, true), (2, 2500, false)]
+        );
+    }
+
+    #[test]
+    fn guesses_apus_without_a_render_node() {
+        let root = FakeRoot::new("amd-apu-fallback");
+        write_memory(&root, "0000:c4:00.0", 512 * MIB, 512 * MIB);
+        write_memory(&root, "0000:03:00.0", 16384 * MIB, 256 * MIB);
+        // the renderD128 entry exists but /dev/dri is missing from the fake root
+        root.mkdir("sys/bus/pci/devices/0000:c4:00.0/drm/renderD128");
+
+        let apu = AmdGpu::from_device(root.path(), &amdgpu("0000:c4:00.0")).unwrap();
+        assert!(apu.is_apu);
+        assert_eq!(apu.total_memory(), 8704 * MIB);
+        assert_eq!(apu.used_memory(), 3 * MIB);
+
+        let discrete = AmdGpu::from_device(root.path(), &amdgpu("0000:03:00.0")).unwrap();
+        assert!(!discrete.is_apu);
+        assert_eq!(discrete.total_memory(), 16384 * MIB);
+        assert_eq!(discrete.used_memory(), MIB);
+    }
+
+    #[test]
+    fn ignores_other_drivers() {
+        let root = FakeRoot::new("amd-other-driver");
+        let mut gpu = amdgpu("0000:03:00.0");
+        gpu.driver = Some("radeon".to_string());
+
+        assert!(AmdGpu::from_device(root.path(), &gpu).is_none());
+    }
+}
@@ -0,0 +1,97 @@
+use crate::gpu::{DriverVersionData, NvidiaGpu, PciGpu};
+
+/// A vendor library that enumerates gpus on its own, such as NVML. `FakeGpuBackend`, behind
+/// the `test-utils` feature, implements it in memory so the merging with sysfs can be tested
+/// without a gpu.
+pub trait GpuBackend {
+    /// Driver version as the library prints it, e.g. `550.54.14`
+    fn driver_version(&self) -> Result<String, Box<dyn std::error::Error>>;
+
+    fn device_count(&self) -> Result<u32, Box<dyn std::error::Error>>;
+
+    /// `driver_version` and `adapter_index` are filled in by `devices`
+    fn device(&self, index: u32) -> Result<NvidiaGpu, Box<dyn std::error::Error>>;
+
+    /// Every device, matched to `adapters` by PCI bus id. Devices that fail, e.g. after
+    /// falling off the bus, are skipped, an unparsable driver version is left as `None`.
+    fn devices(&self, adapters: &[PciGpu]) -> Result<Vec<NvidiaGpu>, Box<dyn std::error::Error>> {
+        let driver_version = self
+            .driver_version()
+            .ok()
+            .and_then(|version| DriverVersionData::parse(&version));
+
+        let mut devices: Vec<NvidiaGpu> = (0..self.device_count()?)
+            .filter_map(|index| self.device(index).ok())
+            .map(|device| NvidiaGpu {
+                driver_version: driver_version.clone(),
+                ..device
+            })
+            .collect();
+
+        NvidiaGpu::assign_adapters(&mut devices, adapters);
+        Ok(devices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::FakeGpuBackend;
+
+    fn device(bus_id: &str, name: &str) -> NvidiaGpu {
+        NvidiaGpu {
+            bus_id: bus_id.to_string(),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn adapter(bus_id: &str) -> PciGpu {
+        PciGpu {
+            bus_id: bus_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn skips_failing_devices() {
+        let backend = FakeGpuBackend::new()
+            .with_driver_version("550.54.14")
+            .with_device(device("0000:01:00.0", "NVIDIA L4"))
+            .with_failing_device()
+            .with_device(device("0000:41:00.0", "NVIDIA A100"));
+
+        let devices = backend
+            .devices(&[adapter("0000:00:02.0"), adapter("0000:41:00.0")])
+            .unwrap();
+        let summary: Vec<(u32, &str, u32)> = devices
+            .iter()
+            .map(|d| (d.index, d.name.as_str(), d.adapter_index))
+            .collect();
+        // the first device is missing from sysfs and numbered after the adapters
+        assert_eq!(summary, vec![(0, "NVIDIA L4", 2), (2, "NVIDIA A100", 1)]);
+        assert!(devices
+            .iter()
+            .all(|d| d.driver_version.as_ref().map(|v| v.major) == Some(550)));
+    }
+
+    #[test]
+    fn leaves_unparsable_driver_versions_unset() {
+        let backend = FakeGpuBackend::new()
+            .with_driver_version("unknown")
+            .with_device(device("0000:01:00.0", "NVIDIA L4"));
+
+        let devices = backend.devices(&[]).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert!(devices[0].driver_version.is_none());
+    }
+
+    #[test]
+    fn fails_when_unavailable() {
+        let backend = FakeGpuBackend::unavailable();
+
+        assert!(backend.driver_version().is_err());
+        assert!(backend.device_count().is_err());
+        assert!(backend.devices(&[adapter("0000:01:00.0")]).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+// Queries the DRM drivers answer through ioctls on the render node, for facts sysfs does not
+// carry. Render nodes are usually accessible to the `render` group, every query returns `None`
+// when the node cannot be opened (containers without /dev/dri, fake sysfs trees in tests).
+
+use std::path::{Path, PathBuf};
+
+use crate::sysfs;
+
+/// `AMDGPU_IDS_FLAGS_FUSION`, set for APUs
+pub(crate) const AMDGPU_IDS_FLAGS_FUSION: u64 = 0x1;
+
+/// `<root>/dev/dri/renderDN` of the PCI device at `device_dir`
+pub(crate) fn render_node(root: &Path, device_dir: &Path) -> Option<PathBuf> {
+    let (index, _) = sysfs::indexed_entries(device_dir.join("drm"), "renderD")
+        .into_iter()
+        .next()?;
+    Some(root.join(format!("dev/dri/renderD{}", index)))
+}
+
+/// A local or system memory region of an i915 device, in bytes
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct I915MemoryRegion {
+    /// `I915_MEMORY_CLASS_DEVICE` for local memory (lmem)
+    pub is_device: bool,
+    pub probed_size: u64,
+    /// Only accounted for callers with CAP_PERFMON, everyone else gets `probed_size`
+    pub unallocated_size: u64,
+}
+
+#[cfg(target_os = "linux")]
+mod ioctl {
+    use std::fs::File;
+    use std::os::fd::AsRawFd;
+    use std::path::Path;
+
+    use super::I915MemoryRegion;
+
+    const DRM_IOCTL_BASE: u64 = b'd' as u64;
+    const DRM_COMMAND_BASE: u64 = 0x40;
+
+    /// `_IOC(dir, 'd', nr, size)` as in the kernel uapi headers
+    const fn drm_ioc(dir: u64, nr: u64, size: usize) -> u64 {
+        (dir << 30) | ((size as u64) << 16) | (DRM_IOCTL_BASE << 8) | (DRM_COMMAND_BASE + nr)
+    }
+
+    const IOC_WRITE: u64 = 1;
+    const IOC_READ_WRITE: u64 = 3;
+
+    /// `struct drm_amdgpu_info`, the query specific union is unused for `DEV_INFO`
+    #[repr(C)]
+    struct DrmAmdgpuInfo {
+        return_pointer: u64,
+        return_size: u32,
+        query: u32,
+        _union: [u32; 4],
+    }
+
+    const DRM_AMDGPU_INFO: u64 = 0x05;
+    const AMDGPU_INFO_DEV_INFO: u32 = 0x16;
+    /// Offset of `ids_flags` in `struct drm_amdgpu_info_device`
+    const IDS_FLAGS_OFFSET: usize = 136;
+
+    /// `struct drm_i915_query`
+    #[repr(C)]
+    struct DrmI915Query {
+        num_items: u32,
+        flags: u32,
+        items_ptr: u64,
+    }
+
+    /// `struct drm_i915_query_item`
+    #[repr(C)]
+    struct DrmI915QueryItem {
+        query_id: u64,
+        length: i32,
+        flags: u32,
+        data_ptr: u64,
+    }
+
+    const DRM_I915_QUERY: u64 = 0x39;
+    const DRM_I915_QUERY_MEMORY_REGIONS: u64 = 4;
+    const I915_MEMORY_CLASS_DEVICE: u16 = 1;
+    /// `struct drm_i915_query_memory_regions` header and `struct drm_i915_memory_region_info`
+    const REGIONS_HEADER_SIZE: usize = 16;
+    const REGION_INFO_SIZE: usize = 88;
+
+    /// Runs `request` on `file`, `arg` must be the struct the request was built for
+    fn ioctl<T>(file: &File, request: u64, arg: &mut T) -> Option<()> {
+        // SAFETY: the request number encodes the size of `T`, which is the layout the
+        // driver expects, and every pointer inside `arg` outlives the call
+        let result = unsafe { libc::ioctl(file.as_raw_fd(), request as _, arg as *mut T) };
+        (result == 0).then_some(())
+    }
+
+    pub(crate) fn amdgpu_ids_flags(node: &Path) -> Option<u64> {
+        let file = File::open(node).ok()?;
+        // the kernel copies min(return_size, sizeof(struct drm_amdgpu_info_device))
+        let mut device_info = [0u8; IDS_FLAGS_OFFSET + 8];
+        let mut info = DrmAmdgpuInfo {
+            return_pointer: device_info.as_mut_ptr() as u64,
+            return_size: device_info.len() as u32,
+            query: AMDGPU_INFO_DEV_INFO,
+            _union: [0; 4],
+        };
+
+        let request = drm_ioc(
+            IOC_WRITE,
+            DRM_AMDGPU_INFO,
+            std::mem::size_of::<DrmAmdgpuInfo>(),
+        );
+        ioctl(&file, request, &mut info)?;
+
+        let flags = device_info[IDS_FLAGS_OFFSET..].try_into().ok()?;
+        Some(u64::from_ne_bytes(flags))
+    }
+
+    pub(crate) fn i915_memory_regions(node: &Path) -> Option<Vec<I915MemoryRegion>> {
+        let file = File::open(node).ok()?;
+        let request = drm_ioc(
+            IOC_READ_WRITE,
+            DRM_I915_QUERY,
+            std::mem::size_of::<DrmI915Query>(),
+        );
+
+        // a zero length asks the kernel for the size of the answer
+        let mut item = DrmI915QueryItem {
+            query_id: DRM_I915_QUERY_MEMORY_REGIONS,
+            length: 0,
+            flags: 0,
+            data_ptr: 0,
+        };
+        let mut query = DrmI915Query {
+            num_items: 1,
+            flags: 0,
+            items_ptr: &mut item as *mut DrmI915QueryItem as u64,
+        };
+        ioctl(&file, request, &mut query)?;
+        // negative lengths are errors such as -ENODEV on kernels without the query
+        if item.length < REGIONS_HEADER_SIZE as i32 {
+            return None;
+        }
+
+        let mut data = vec![0u8; item.length as usize];
+        item.data_ptr = data.as_mut_ptr() as u64;
+        query.items_ptr = &mut item as *mut DrmI915QueryItem as u64;
+        ioctl(&file, request, &mut query)?;
+        if item.length < REGIONS_HEADER_SIZE as i32 {
+            return None;
+        }
+
+        let u16_at = |offset: usize| {
+            Some(u16::from_ne_bytes(
+                data.get(offset..offset + 2)?.try_into().ok()?,
+            ))
+        };
+        let u32_at = |offset: usize| {
+            Some(u32::from_ne_bytes(
+                data.get(offset..offset + 4)?.try_into().ok()?,
+            ))
+        };
+        let u64_at = |offset: usize| {
+            Some(u64::from_ne_bytes(
+                data.get(offset..offset + 8)?.try_into().ok()?,
+            ))
+        };
+
+        (0..u32_at(0)? as usize)
+            .map(|index| {
+                let region = REGIONS_HEADER_SIZE + index * REGION_INFO_SIZE;
+                Some(I915MemoryRegion {
+                    is_device: u16_at(region)? == I915_MEMORY_CLASS_DEVICE,
+                    probed_size: u64_at(region + 8)?,
+                    unallocated_size: u64_at(region + 16)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `ids_flags` of the amdgpu device info, see `AMDGPU_IDS_FLAGS_FUSION`
+pub(crate) fn amdgpu_ids_flags(node: &Path) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        ioctl::amdgpu_ids_flags(node)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = node;
+        None
+    }
+}
+
+/// Memory regions reported by `DRM_I915_QUERY_MEMORY_REGIONS`, kernels before 5.16 (and
+/// 5.x kernels without lmem support) do not know the query
+pub(crate) fn i915_memory_regions(node: &Path) -> Option<Vec<I915MemoryRegion>> {
+    #[cfg(target_os = "linux")]
+    {
+        ioctl::i915_memory_regions(node)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = node;
+        None
+    }
+}
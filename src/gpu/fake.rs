@@ -0,0 +1,84 @@
+use crate::gpu::{GpuBackend, NvidiaGpu};
+
+/// A `GpuBackend` answering from memory
+#[derive(Debug, Clone)]
+pub struct FakeGpuBackend {
+    available: bool,
+    driver_version: Option<String>,
+    /// `None` for a device whose queries fail
+    devices: Vec<Option<NvidiaGpu>>,
+}
+
+impl Default for FakeGpuBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeGpuBackend {
+    /// A working backend without devices
+    pub fn new() -> Self {
+        Self {
+            available: true,
+            driver_version: None,
+            devices: Vec::new(),
+        }
+    }
+
+    /// A backend whose library failed to load, e.g. without the NVIDIA driver
+    pub fn unavailable() -> Self {
+        Self {
+            available: false,
+            ..Self::new()
+        }
+    }
+
+    pub fn with_driver_version(mut self, version: &str) -> Self {
+        self.driver_version = Some(version.to_string());
+        self
+    }
+
+    /// Adds a device, its `index` is set to its position
+    pub fn with_device(mut self, device: NvidiaGpu) -> Self {
+        let index = self.devices.len() as u32;
+        self.devices.push(Some(NvidiaGpu { index, ..device }));
+        self
+    }
+
+    /// Adds a device that is counted but fails every query
+    pub fn with_failing_device(mut self) -> Self {
+        self.devices.push(None);
+        self
+    }
+
+    fn check_available(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.available {
+            Ok(())
+        } else {
+            Err("gpu backend is not available".into())
+        }
+    }
+}
+
+impl GpuBackend for FakeGpuBackend {
+    fn driver_version(&self) -> Result<String, Box<dyn std::error::Error>> {
+        self.check_available()?;
+        self.driver_version
+            .clone()
+            .ok_or_else(|| "no driver version".into())
+    }
+
+    fn device_count(&self) -> Result<u32, Box<dyn std::error::Error>> {
+        self.check_available()?;
+        Ok(self.devices.len() as u32)
+    }
+
+    fn device(&self, index: u32) -> Result<NvidiaGpu, Box<dyn std::error::Error>> {
+        self.check_available()?;
+        self.devices
+            .get(index as usize)
+            .cloned()
+            .flatten()
+            .ok_or_else(|| format!("device {} is not available", index).into())
+    }
+}
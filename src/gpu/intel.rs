@@ -0,0 +1,276 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::gpu::{drm, PciGpu};
+use crate::sysfs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntelDriver {
+    I915,
+    Xe,
+}
+
+/// A graphics or media tile of an Intel gpu, frequencies are in MHz
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntelGt {
+    pub id: u32,
+    /// Frequency requested by the driver
+    pub cur_freq: Option<u32>,
+    /// Frequency the hardware actually runs at, 0 while in RC6
+    pub act_freq: Option<u32>,
+    pub min_freq: Option<u32>,
+    pub max_freq: Option<u32>,
+    /// Highest frequency the hardware supports
+    pub rp0_freq: Option<u32>,
+    /// Lowest frequency the hardware supports
+    pub rpn_freq: Option<u32>,
+    /// Time spent in the RC6 power saving state since boot, in milliseconds
+    pub rc6_residency_ms: Option<u64>,
+}
+
+impl IntelGt {
+    /// Share of `elapsed` spent outside of RC6 since `earlier`, in percent. The gt only
+    /// leaves RC6 to run work, which makes this a rough busy figure.
+    pub fn busy_percent(&self, earlier: &IntelGt, elapsed: Duration) -> Option<f32> {
+        let (now, before) = (self.rc6_residency_ms?, earlier.rc6_residency_ms?);
+        if elapsed.is_zero() {
+            return None;
+        }
+
+        let idle = now.saturating_sub(before) as f64 / elapsed.as_secs_f64() / 10.0;
+        Some((100.0 - idle).clamp(0.0, 100.0) as f32)
+    }
+}
+
+/// Details of a gpu driven by i915 or xe, sizes are in bytes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntelGpu {
+    pub bus_id: String,
+    pub adapter_index: u32,
+    pub driver: IntelDriver,
+    /// Integrated gpus share system memory, Arc cards have local memory (lmem)
+    pub is_integrated: bool,
+    /// `None` for integrated gpus, and for discrete cards on i915 kernels that have neither
+    /// the memory regions query (5.16+) nor the out of tree `lmem_total_bytes`
+    pub lmem_total: Option<u64>,
+    /// Only known to callers with CAP_PERFMON (or the out of tree `lmem_avail_bytes`), and
+    /// never reported by xe
+    pub lmem_available: Option<u64>,
+    pub gts: Vec<IntelGt>,
+}
+
+impl IntelGpu {
+    /// All i915 and xe devices under `root`, `adapter_index` matches `PciGpu::read_adapters`
+    pub fn read_all<P: AsRef<Path>>(root: P) -> Vec<Self> {
+        let root = root.as_ref();
+        PciGpu::read_all(root)
+            .iter()
+            .enumerate()
+            .filter_map(|(index, gpu)| {
+                let mut intel = Self::from_device(root, gpu)?;
+                intel.adapter_index = index as u32;
+                Some(intel)
+            })
+            .collect()
+    }
+
+    /// `None` unless the device is bound to i915 or xe
+    pub fn from_device<P: AsRef<Path>>(root: P, gpu: &PciGpu) -> Option<Self> {
+        let root = root.as_ref();
+        let driver = match gpu.driver.as_deref() {
+            Some("i915") => IntelDriver::I915,
+            Some("xe") => IntelDriver::Xe,
+            _ => return None,
+        };
+
+        let dir = gpu.sysfs_path(root);
+        let card = gpu
+            .card
+            .map(|card| root.join(format!("sys/class/drm/card{}", card)));
+
+        let (lmem_total, lmem_available, gts) = match driver {
+            IntelDriver::I915 => {
+                let (total, available) = drm::render_node(root, &dir)
+                    .and_then(|node| drm::i915_memory_regions(&node))
+                    .map(|regions| Self::i915_lmem(&regions))
+                    .unwrap_or_default();
+                // only exposed by kernels carrying the DG2 lmem patches
+                let lmem = |file: &str| card.as_ref().and_then(|c| sysfs::read_u64(c.join(file)));
+                (
+                    total.or_else(|| lmem("lmem_total_bytes")),
+                    available.or_else(|| lmem("lmem_avail_bytes")),
+                    card.as_deref().map(Self::i915_gts).unwrap_or_default(),
+                )
+            }
+            IntelDriver::Xe => {
+                let tiles = sysfs::indexed_entries(&dir, "tile");
+                let vram: Vec<u64> = tiles
+                    .iter()
+                    .filter_map(|(_, tile)| sysfs::read_u64(tile.join("physical_vram_size_bytes")))
+                    .collect();
+                let gts = tiles
+                    .iter()
+                    .flat_map(|(_, tile)| sysfs::indexed_entries(tile, "gt"))
+                    .map(|(id, gt)| Self::xe_gt(id, &gt))
+                    .collect();
+                ((!vram.is_empty()).then(|| vram.iter().sum()), None, gts)
+            }
+        };
+
+        Some(IntelGpu {
+            bus_id: gpu.bus_id.clone(),
+            adapter_index: 0,
+            driver,
+            // both drivers register hwmon for discrete cards only, which covers the cards
+            // whose lmem size could not be read
+            is_integrated: lmem_total.is_none() && !dir.join("hwmon").is_dir(),
+            lmem_total,
+            lmem_available,
+            gts,
+        })
+    }
+
+    /// Total and unallocated local memory. Without CAP_PERFMON the kernel reports the
+    /// probed size as unallocated, which is then treated as unknown.
+    fn i915_lmem(regions: &[drm::I915MemoryRegion]) -> (Option<u64>, Option<u64>) {
+        let lmem: Vec<&drm::I915MemoryRegion> = regions.iter().filter(|r| r.is_device).collect();
+        if lmem.is_empty() {
+            return (None, None);
+        }
+
+        let total = lmem.iter().map(|r| r.probed_size).sum();
+        let unallocated = lmem.iter().map(|r| r.unallocated_size).sum();
+        (Some(total), (unallocated != total).then_some(unallocated))
+    }
+
+    /// `cardN/gt/gtM/rps_*_freq_mhz`, kernels before 5.17 only have the card wide
+    /// `gt_*_freq_mhz` files
+    fn i915_gts(card: &Path) -> Vec<IntelGt> {
+        let freq = |dir: &Path, file: String| sysfs::read_u64(dir.join(file)).map(|f| f as u32);
+
+        let gts: Vec<IntelGt> = sysfs::indexed_entries(card.join("gt"), "gt")
+            .into_iter()
+            .map(|(id, gt)| {
+                let rps = |name: &str| freq(&gt, format!("rps_{}_freq_mhz", name));
+                IntelGt {
+                    id,
+                    cur_freq: rps("cur"),
+                    act_freq: rps("act"),
+                    min_freq: rps("min"),
+                    max_freq: rps("max"),
+                    rp0_freq: rps("RP0"),
+                    rpn_freq: rps("RPn"),
+                    rc6_residency_ms: sysfs::read_u64(gt.join("rc6_residency_ms")),
+                }
+            })
+            .collect();
+        if !gts.is_empty() {
+            return gts;
+        }
+
+        let gt = |name: &str| freq(card, format!("gt_{}_freq_mhz", name));
+        let legacy = IntelGt {
+            id: 0,
+            cur_freq: gt("cur"),
+            act_freq: gt("act"),
+            min_freq: gt("min"),
+            max_freq: gt("max"),
+            rp0_freq: gt("RP0"),
+            rpn_freq: gt("RPn"),
+            rc6_residency_ms: sysfs::read_u64(card.join("power/rc6_residency_ms")),
+        };
+        if legacy.cur_freq.is_some() {
+            vec![legacy]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// `device/tileN/gtM/freq0/*_freq` and the gt idle residency, which is RC6 on xe
+    fn xe_gt(id: u32, gt: &Path) -> IntelGt {
+        let freq = |name: &str| {
+            sysfs::read_u64(gt.join("freq0").join(format!("{}_freq", name))).map(|f| f as u32)
+        };
+
+        IntelGt {
+            id,
+            cur_freq: freq("cur"),
+            act_freq: freq("act"),
+            min_freq: freq("min"),
+            max_freq: freq("max"),
+            rp0_freq: freq("rp0"),
+            rpn_freq: freq("rpn"),
+            rc6_residency_ms: sysfs::read_u64(gt.join("gtidle/idle_residency_ms")),
+        }
+    }
+}
+
+// the fake trees need `:` in file names
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn i915(bus_id: &str, card: u32) -> PciGpu {
+        PciGpu {
+            bus_id: bus_id.to_string(),
+            vendor_id: 0x8086,
+            driver: Some("i915".to_string()),
+            card: Some(card),
+            ..Default::default()
+        }
+    }
+
+    fn region(is_device: bool, probed_size: u64, unallocated_size: u64) -> drm::I915MemoryRegion {
+        drm::I915MemoryRegion {
+            is_device,
+            probed_size,
+            unallocated_size,
+        }
+    }
+
+    #[test]
+    fn sums_local_memory_regions() {
+        let system = region(false, 32 * GIB, 20 * GIB);
+
+        assert_eq!(
+            IntelGpu::i915_lmem(&[system, region(true, 16 * GIB, 10 * GIB)]),
+            (Some(16 * GIB), Some(10 * GIB))
+        );
+        // without CAP_PERFMON unallocated reads as the probed size
+        assert_eq!(
+            IntelGpu::i915_lmem(&[system, region(true, 8 * GIB, 8 * GIB)]),
+            (Some(8 * GIB), None)
+        );
+        assert_eq!(IntelGpu::i915_lmem(&[system]), (None, None));
+    }
+
+    #[test]
+    fn tells_discrete_cards_without_lmem_apart() {
+        let root = FakeRoot::new("intel-discrete");
+        root.mkdir("sys/bus/pci/devices/0000:00:02.0")
+            .mkdir("sys/bus/pci/devices/0000:03:00.0/hwmon/hwmon4")
+            .write("sys/class/drm/card2/lmem_total_bytes", "17179869184\n")
+            .write("sys/class/drm/card2/lmem_avail_bytes", "4294967296\n");
+
+        let integrated = IntelGpu::from_device(root.path(), &i915("0000:00:02.0", 0)).unwrap();
+        assert!(integrated.is_integrated);
+        assert_eq!(integrated.lmem_total, None);
+
+        // an Arc card on a kernel without the memory regions query
+        let discrete = IntelGpu::from_device(root.path(), &i915("0000:03:00.0", 1)).unwrap();
+        assert!(!discrete.is_integrated);
+        assert_eq!(discrete.lmem_total, None);
+        assert_eq!(discrete.lmem_available, None);
+
+        // out of tree kernels report the lmem size in sysfs
+        let patched = IntelGpu::from_device(root.path(), &i915("0000:04:00.0", 2)).unwrap();
+        assert!(!patched.is_integrated);
+        assert_eq!(patched.lmem_total, Some(16 * GIB));
+        assert_eq!(patched.lmem_available, Some(4 * GIB));
+    }
+}
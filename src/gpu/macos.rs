@@ -1,4 +1,4 @@
-use crate::gpu::{AdapterData, DriverVersionData, GPUData, GPUUsage};
+use crate::gpu::{AdapterData, AmdGpu, DriverVersionData, GPUData, GPUUsage, IntelGpu, NvidiaGpu};
 use objc2::msg_send;
 use objc2_metal::{MTLCreateSystemDefaultDevice, MTLDevice};
 use os_version::OsVersion;
@@ -83,6 +83,18 @@ impl GPUUsage {
         Ok(results)
     }
 
+    pub fn get_amd_gpus() -> Result<Vec<AmdGpu>, Box<dyn std::error::Error>> {
+        Err("amdgpu is not available on macOS".into())
+    }
+
+    pub fn get_intel_gpus() -> Result<Vec<IntelGpu>, Box<dyn std::error::Error>> {
+        Err("i915 and xe are not available on macOS".into())
+    }
+
+    pub fn get_nvidia_gpus() -> Result<Vec<NvidiaGpu>, Box<dyn std::error::Error>> {
+        Err("NVML is not available on macOS".into())
+    }
+
     pub fn total_gpu_memory() -> Result<u64, Box<dyn std::error::Error>> {
         unsafe {
             let mtl_device = MTLCreateSystemDefaultDevice();
@@ -1,5 +1,26 @@
 use serde::{Deserialize, Serialize};
 
+mod amd;
+mod backend;
+mod drm;
+#[cfg(any(test, feature = "test-utils"))]
+mod fake;
+mod intel;
+mod nvidia;
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+mod nvml;
+mod pci;
+
+pub use amd::{AmdGpu, DpmLevel};
+pub use backend::GpuBackend;
+#[cfg(any(test, feature = "test-utils"))]
+pub use fake::FakeGpuBackend;
+pub use intel::{IntelDriver, IntelGpu, IntelGt};
+pub use nvidia::{NvidiaClocks, NvidiaGpu};
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+pub use nvml::NvmlBackend;
+pub use pci::PciGpu;
+
 #[cfg(target_os = "macos")]
 mod macos;
 #[cfg(target_os = "windows")]
@@ -10,7 +31,7 @@ mod linux;
 
 pub struct GPUUsage;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DriverVersionData {
     pub major: u64,
     pub minor: u64,
@@ -18,6 +39,27 @@ pub struct DriverVersionData {
     pub revision: u64,
 }
 
+impl DriverVersionData {
+    /// Parses dotted versions such as `550.54.14` or `32.0.15.6094`, trailing text in a
+    /// component (`1.2.3-rc1`) is ignored. `None` when the first component is not a number.
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().split('.').map(|part| {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse::<u64>().ok()
+        });
+
+        let major = parts.next()??;
+        let mut next = || parts.next().flatten().unwrap_or(0);
+
+        Some(DriverVersionData {
+            major,
+            minor: next(),
+            build: next(),
+            revision: next(),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdapterData {
     pub name: String,
@@ -67,6 +109,12 @@ impl AdapterData {
     }
 }
 
+impl Default for GPUData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GPUData {
     pub fn new() -> Self {
         Self {
@@ -88,6 +136,7 @@ impl GPUData {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new_with_values(
         name: String,
         architecture: String,
@@ -113,9 +162,45 @@ impl GPUData {
             driver_version,
         }
     }
+
+    /// Base values from an adapter, usage figures are left at 0
+    pub fn from_adapter(adapter: &AdapterData) -> Self {
+        Self {
+            name: adapter.name.clone(),
+            architecture: adapter.architecture.clone(),
+            vendor_id: adapter.vendor_id,
+            total_memory: adapter.total_memory,
+            free_memory: 0,
+            used_memory: 0,
+            has_unified_memory: adapter.is_integrated,
+            is_integrated: adapter.is_integrated,
+            adapter_index: adapter.adapter_index,
+            driver_version: adapter.driver_version.clone(),
+        }
+    }
+
     pub fn is_high_memory_dedicated(&self) -> bool {
         // Consider GPU as high memory if it has 4GB (4 * 1024 * 1024 * 1024 bytes) or more
         let four_gb = 4 * 1024 * 1024 * 1024;
         !self.is_integrated && self.total_memory >= four_gb
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(version: &str) -> Option<(u64, u64, u64, u64)> {
+        DriverVersionData::parse(version).map(|v| (v.major, v.minor, v.build, v.revision))
+    }
+
+    #[test]
+    fn parses_driver_versions() {
+        assert_eq!(parse("550.54.14"), Some((550, 54, 14, 0)));
+        assert_eq!(parse("32.0.15.6094\n"), Some((32, 0, 15, 6094)));
+        assert_eq!(parse("535"), Some((535, 0, 0, 0)));
+        assert_eq!(parse("1.2.3-rc1"), Some((1, 2, 3, 0)));
+        assert_eq!(parse("NVIDIA driver"), None);
+        assert_eq!(parse(""), None);
+    }
+}
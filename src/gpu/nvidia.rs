@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+
+use crate::gpu::pci::{vendor_name, VENDOR_NVIDIA};
+use crate::gpu::{DriverVersionData, GPUData, PciGpu};
+
+/// Clock domains of an NVIDIA gpu, in MHz
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct NvidiaClocks {
+    pub graphics: Option<u32>,
+    pub sm: Option<u32>,
+    pub memory: Option<u32>,
+}
+
+/// An NVIDIA gpu as reported by NVML, sizes are in bytes. Queries the device does not
+/// support, e.g. ECC on GeForce cards or MIG before Ampere, are `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NvidiaGpu {
+    /// NVML device index, follows `CUDA_DEVICE_ORDER=PCI_BUS_ID`
+    pub index: u32,
+    /// Index of the matching `PciGpu`, devices missing from sysfs are numbered after it
+    pub adapter_index: u32,
+    pub uuid: String,
+    /// In sysfs form, e.g. `0000:01:00.0`
+    pub bus_id: String,
+    pub name: String,
+    pub architecture: Option<String>,
+    pub driver_version: Option<DriverVersionData>,
+    /// CUDA compute capability as (major, minor)
+    pub compute_capability: Option<(u32, u32)>,
+    pub sm_count: Option<u32>,
+    pub clocks: NvidiaClocks,
+    pub max_clocks: NvidiaClocks,
+    /// in percent
+    pub gpu_utilization: Option<u32>,
+    /// Time the memory controller was busy, in percent
+    pub memory_utilization: Option<u32>,
+    pub memory_total: u64,
+    pub memory_used: u64,
+    pub memory_free: u64,
+    /// in degrees Celsius
+    pub temperature: Option<u32>,
+    /// in watts
+    pub power_usage: Option<f32>,
+    /// Enforced power limit in watts
+    pub power_limit: Option<f32>,
+    pub ecc_enabled: Option<bool>,
+    pub mig_enabled: Option<bool>,
+}
+
+impl NvidiaGpu {
+    /// NVML prints the PCI domain with 8 digits (`00000000:01:00.0`), sysfs with 4
+    pub fn normalize_bus_id(bus_id: &str) -> String {
+        let bus_id = bus_id.trim().to_lowercase();
+        match bus_id.split_once(':') {
+            Some((domain, rest)) if domain.len() > 4 => {
+                format!("{}:{}", &domain[domain.len() - 4..], rest)
+            }
+            _ => bus_id,
+        }
+    }
+
+    /// Sets `adapter_index` to the position of the `PciGpu` with the same bus id. Devices
+    /// whose bus id could not be queried are matched by their NVML index to the cards bound
+    /// to `nvidia`, both are ordered by bus id.
+    pub fn assign_adapters(devices: &mut [NvidiaGpu], adapters: &[PciGpu]) {
+        let nvidia: Vec<usize> = adapters
+            .iter()
+            .enumerate()
+            .filter(|(_, adapter)| adapter.driver.as_deref() == Some("nvidia"))
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut next = adapters.len() as u32;
+        for device in devices.iter_mut() {
+            let position = if device.bus_id.is_empty() {
+                nvidia.get(device.index as usize).copied()
+            } else {
+                adapters.iter().position(|a| a.bus_id == device.bus_id)
+            };
+            device.adapter_index = match position {
+                Some(index) => index as u32,
+                None => {
+                    let index = next;
+                    next += 1;
+                    index
+                }
+            };
+        }
+    }
+
+    /// Overrides the sysfs values of `gpu` with the ones from NVML
+    pub fn apply(&self, gpu: &mut GPUData) {
+        gpu.name = self.name.clone();
+        if let Some(architecture) = &self.architecture {
+            gpu.architecture = architecture.clone();
+        }
+        gpu.vendor_id = VENDOR_NVIDIA;
+        // the sysfs guess can take a passthrough card on the root bus of a VM for integrated
+        gpu.is_integrated = false;
+        gpu.has_unified_memory = false;
+        gpu.total_memory = self.memory_total;
+        gpu.used_memory = self.memory_used;
+        gpu.free_memory = self.memory_free;
+        gpu.adapter_index = self.adapter_index;
+        if let Some(driver_version) = &self.driver_version {
+            gpu.driver_version = driver_version.clone();
+        }
+    }
+
+    pub fn to_gpu_data(&self) -> GPUData {
+        let mut gpu = GPUData {
+            architecture: vendor_name(VENDOR_NVIDIA).to_string(),
+            ..GPUData::new()
+        };
+        self.apply(&mut gpu);
+        gpu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(bus_id: &str) -> NvidiaGpu {
+        NvidiaGpu {
+            bus_id: bus_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn adapter(bus_id: &str) -> PciGpu {
+        PciGpu {
+            bus_id: bus_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalizes_bus_ids() {
+        assert_eq!(
+            NvidiaGpu::normalize_bus_id("00000000:01:00.0"),
+            "0000:01:00.0"
+        );
+        assert_eq!(
+            NvidiaGpu::normalize_bus_id("00000001:C1:00.0\n"),
+            "0001:c1:00.0"
+        );
+        assert_eq!(NvidiaGpu::normalize_bus_id("0000:41:00.0"), "0000:41:00.0");
+    }
+
+    #[test]
+    fn assigns_adapters_by_bus_id() {
+        let adapters = [
+            adapter("0000:00:02.0"),
+            adapter("0000:01:00.0"),
+            adapter("0000:41:00.0"),
+        ];
+        let mut devices = [
+            device("0000:41:00.0"),
+            device("0000:81:00.0"),
+            device("0000:01:00.0"),
+            device("0000:c1:00.0"),
+        ];
+
+        NvidiaGpu::assign_adapters(&mut devices, &adapters);
+        let indices: Vec<u32> = devices.iter().map(|d| d.adapter_index).collect();
+        // devices sysfs does not list are numbered after the adapters, in NVML order
+        assert_eq!(indices, vec![2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn matches_devices_without_a_bus_id_by_index() {
+        let nvidia = |bus_id: &str| PciGpu {
+            driver: Some("nvidia".to_string()),
+            ..adapter(bus_id)
+        };
+        let adapters = [
+            adapter("0000:00:02.0"),
+            nvidia("0000:01:00.0"),
+            nvidia("0000:41:00.0"),
+        ];
+        let mut devices = [
+            NvidiaGpu {
+                index: 0,
+                ..device("0000:01:00.0")
+            },
+            // pci_info failed for the second device
+            NvidiaGpu {
+                index: 1,
+                ..device("")
+            },
+            NvidiaGpu {
+                index: 2,
+                ..device("")
+            },
+        ];
+
+        NvidiaGpu::assign_adapters(&mut devices, &adapters);
+        let indices: Vec<u32> = devices.iter().map(|d| d.adapter_index).collect();
+        assert_eq!(indices, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn applies_as_a_discrete_card() {
+        let nvidia = NvidiaGpu {
+            name: "NVIDIA L4".to_string(),
+            memory_total: 24 << 30,
+            memory_used: 1 << 30,
+            memory_free: 23 << 30,
+            adapter_index: 2,
+            ..Default::default()
+        };
+        let mut gpu = GPUData {
+            is_integrated: true,
+            has_unified_memory: true,
+            ..GPUData::new()
+        };
+
+        nvidia.apply(&mut gpu);
+        assert!(!gpu.is_integrated);
+        assert!(!gpu.has_unified_memory);
+        assert_eq!(gpu.name, "NVIDIA L4");
+        assert_eq!(gpu.vendor_id, VENDOR_NVIDIA);
+        assert_eq!(gpu.total_memory, 24 << 30);
+        assert_eq!(gpu.adapter_index, 2);
+    }
+}
@@ -0,0 +1,87 @@
+use nvml_wrapper::enum_wrappers::device::{Clock, TemperatureSensor};
+use nvml_wrapper::Nvml;
+
+use crate::gpu::{GpuBackend, NvidiaClocks, NvidiaGpu};
+
+/// `GpuBackend` over the NVIDIA Management Library shipped with the driver
+pub struct NvmlBackend {
+    nvml: Nvml,
+}
+
+impl NvmlBackend {
+    /// Fails when libnvidia-ml cannot be loaded, e.g. without the NVIDIA driver
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            nvml: Nvml::init()?,
+        })
+    }
+}
+
+impl GpuBackend for NvmlBackend {
+    fn driver_version(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(self.nvml.sys_driver_version()?)
+    }
+
+    fn device_count(&self) -> Result<u32, Box<dyn std::error::Error>> {
+        Ok(self.nvml.device_count()?)
+    }
+
+    /// Queries the device does not support are left as `None`, e.g. the architecture of
+    /// chips newer than the NVML headers
+    fn device(&self, index: u32) -> Result<NvidiaGpu, Box<dyn std::error::Error>> {
+        let device = self.nvml.device_by_index(index)?;
+        let memory = device.memory_info().ok();
+        let clocks = |max: bool| {
+            let clock = |clock: Clock| {
+                if max {
+                    device.max_clock_info(clock).ok()
+                } else {
+                    device.clock_info(clock).ok()
+                }
+            };
+            NvidiaClocks {
+                graphics: clock(Clock::Graphics),
+                sm: clock(Clock::SM),
+                memory: clock(Clock::Memory),
+            }
+        };
+        let utilization = device.utilization_rates().ok();
+
+        Ok(NvidiaGpu {
+            index,
+            adapter_index: 0,
+            uuid: device.uuid().unwrap_or_default(),
+            bus_id: device
+                .pci_info()
+                .map(|pci| NvidiaGpu::normalize_bus_id(&pci.bus_id))
+                .unwrap_or_default(),
+            name: device.name().unwrap_or_default(),
+            architecture: device.architecture().ok().map(|a| a.to_string()),
+            driver_version: None,
+            compute_capability: device
+                .cuda_compute_capability()
+                .ok()
+                .map(|cc| (cc.major as u32, cc.minor as u32)),
+            sm_count: device.attributes().ok().map(|a| a.multiprocessor_count),
+            clocks: clocks(false),
+            max_clocks: clocks(true),
+            gpu_utilization: utilization.as_ref().map(|u| u.gpu),
+            memory_utilization: utilization.as_ref().map(|u| u.memory),
+            memory_total: memory.as_ref().map(|m| m.total).unwrap_or(0),
+            memory_used: memory.as_ref().map(|m| m.used).unwrap_or(0),
+            memory_free: memory.as_ref().map(|m| m.free).unwrap_or(0),
+            temperature: device.temperature(TemperatureSensor::Gpu).ok(),
+            // NVML reports milliwatts
+            power_usage: device.power_usage().ok().map(|mw| mw as f32 / 1000.0),
+            power_limit: device
+                .enforced_power_limit()
+                .ok()
+                .map(|mw| mw as f32 / 1000.0),
+            ecc_enabled: device
+                .is_ecc_enabled()
+                .ok()
+                .map(|ecc| ecc.currently_enabled),
+            mig_enabled: device.mig_mode().ok().map(|mig| mig.current == 1),
+        })
+    }
+}
@@ -0,0 +1,519 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::gpu::{AdapterData, AmdGpu, DriverVersionData, GPUData, GpuBackend, IntelGpu};
+use crate::memory::ProcMemInfo;
+use crate::sysfs;
+
+pub(crate) const VENDOR_NVIDIA: u32 = 0x10DE;
+pub(crate) const VENDOR_AMD: u32 = 0x1002;
+pub(crate) const VENDOR_INTEL: u32 = 0x8086;
+
+/// A display controller (PCI class 0x03: VGA, XGA, 3D or other display) found in sysfs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PciGpu {
+    /// e.g. `0000:01:00.0`
+    pub bus_id: String,
+    pub name: String,
+    pub class: u32,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub subsystem_vendor_id: Option<u32>,
+    pub subsystem_device_id: Option<u32>,
+    pub revision: Option<u32>,
+    /// Bound kernel driver, e.g. `nvidia`, `amdgpu`, `i915`, `xe`
+    pub driver: Option<String>,
+    /// The device the firmware used for the boot console
+    pub boot_vga: bool,
+    /// N of /sys/class/drm/cardN, `None` for devices without a DRM driver
+    pub card: Option<u32>,
+}
+
+impl PciGpu {
+    /// Lists the display controllers under `<root>/sys/bus/pci/devices`, ordered by bus id
+    pub fn read_all<P: AsRef<Path>>(root: P) -> Vec<Self> {
+        let root = root.as_ref();
+        let entries = match std::fs::read_dir(root.join("sys/bus/pci/devices")) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let cards = Self::drm_cards(root);
+        let pci_ids = Self::read_pci_ids(root);
+
+        let mut gpus: Vec<PciGpu> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let dir = entry.path();
+                let class = sysfs::read_hex(dir.join("class"))?;
+                if class >> 16 != 0x03 {
+                    return None;
+                }
+
+                let bus_id = entry.file_name().to_string_lossy().to_string();
+                let vendor_id = sysfs::read_hex(dir.join("vendor"))?;
+                let device_id = sysfs::read_hex(dir.join("device"))?;
+
+                Some(PciGpu {
+                    name: Self::lookup_name(pci_ids.as_deref(), vendor_id, device_id),
+                    class,
+                    vendor_id,
+                    device_id,
+                    subsystem_vendor_id: sysfs::read_hex(dir.join("subsystem_vendor")),
+                    subsystem_device_id: sysfs::read_hex(dir.join("subsystem_device")),
+                    revision: sysfs::read_hex(dir.join("revision")),
+                    driver: link_name(&dir.join("driver")),
+                    boot_vga: sysfs::read_bool(dir.join("boot_vga")).unwrap_or(false),
+                    card: cards
+                        .iter()
+                        .find(|(_, card_bus_id)| *card_bus_id == bus_id)
+                        .map(|(card, _)| *card),
+                    bus_id,
+                })
+            })
+            .collect();
+
+        gpus.sort_by(|a, b| a.bus_id.cmp(&b.bus_id));
+        gpus
+    }
+
+    /// `AdapterData` of every display controller, `adapter_index` follows the bus id order
+    pub fn read_adapters<P: AsRef<Path>>(root: P) -> Vec<AdapterData> {
+        let root = root.as_ref();
+        Self::read_all(root)
+            .iter()
+            .enumerate()
+            .map(|(index, gpu)| gpu.to_adapter_data(root, index as u32))
+            .collect()
+    }
+
+    /// `GPUData` of every display controller, with the details of `backend` for the devices
+    /// it reports. High memory dedicated gpus come first, like on Windows.
+    pub fn read_gpus<P: AsRef<Path>>(root: P, backend: Option<&dyn GpuBackend>) -> Vec<GPUData> {
+        let root = root.as_ref();
+        let adapters = Self::read_all(root);
+        let mut gpus: Vec<GPUData> = adapters
+            .iter()
+            .enumerate()
+            .map(|(index, gpu)| gpu.to_gpu_data(root, index as u32))
+            .collect();
+
+        // when the backend fails the cards keep their sysfs values
+        let devices = backend.and_then(|backend| backend.devices(&adapters).ok());
+        for device in devices.unwrap_or_default() {
+            match gpus.get_mut(device.adapter_index as usize) {
+                Some(gpu) => device.apply(gpu),
+                None => gpus.push(device.to_gpu_data()),
+            }
+        }
+
+        // stable sort
+        gpus.sort_by_key(|gpu| !gpu.is_high_memory_dedicated());
+        gpus
+    }
+
+    /// (N, bus id) of every /sys/class/drm/cardN, connectors such as `card0-DP-1` are skipped
+    fn drm_cards(root: &Path) -> Vec<(u32, String)> {
+        sysfs::indexed_entries(root.join("sys/class/drm"), "card")
+            .into_iter()
+            .filter_map(|(index, dir)| Some((index, link_name(&dir.join("device"))?)))
+            .collect()
+    }
+
+    /// The pci.ids database lspci uses, when installed
+    fn read_pci_ids(root: &Path) -> Option<String> {
+        [
+            "usr/share/hwdata/pci.ids",
+            "usr/share/misc/pci.ids",
+            "usr/share/pci.ids",
+        ]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(root.join(path)).ok())
+    }
+
+    /// `<vendor> <device>` from pci.ids like lspci prints it, e.g.
+    /// `NVIDIA Corporation AD102 [GeForce RTX 4090]`
+    fn lookup_name(pci_ids: Option<&str>, vendor_id: u32, device_id: u32) -> String {
+        let fallback = || format!("{} GPU {:04x}", vendor_name(vendor_id), device_id);
+        let pci_ids = match pci_ids {
+            Some(pci_ids) => pci_ids,
+            None => return fallback(),
+        };
+
+        let vendor_key = format!("{:04x}  ", vendor_id);
+        let device_key = format!("\t{:04x}  ", device_id);
+
+        let mut lines = pci_ids
+            .lines()
+            .skip_while(|line| !line.starts_with(&vendor_key));
+        let vendor = match lines.next() {
+            Some(line) => line[vendor_key.len()..].trim(),
+            None => return fallback(),
+        };
+
+        // devices are indented by one tab, the vendor block ends at the next unindented line
+        lines
+            .take_while(|line| line.starts_with('\t') || line.starts_with('#') || line.is_empty())
+            .find_map(|line| line.strip_prefix(&device_key))
+            .map(|device| format!("{} {}", vendor, device.trim()))
+            .unwrap_or_else(fallback)
+    }
+
+    pub fn sysfs_path<P: AsRef<Path>>(&self, root: P) -> PathBuf {
+        root.as_ref().join("sys/bus/pci/devices").join(&self.bus_id)
+    }
+
+    /// Version of the bound driver module, only out of tree drivers such as `nvidia`
+    /// report one
+    pub fn driver_version<P: AsRef<Path>>(&self, root: P) -> Option<DriverVersionData> {
+        let driver = self.driver.as_ref()?;
+        let version = sysfs::read_string(
+            root.as_ref()
+                .join("sys/module")
+                .join(driver)
+                .join("version"),
+        )?;
+        DriverVersionData::parse(&version)
+    }
+
+    /// Integrated gpus of the usual desktop layout sit on the root bus, discrete cards behind
+    /// a PCIe bridge. This does not hold in VMs, where passthrough cards are often attached
+    /// to the root bus (e.g. `0000:00:1e.0` on AWS, `0000:00:04.0` on GCP).
+    pub fn is_on_root_bus(&self) -> bool {
+        self.bus_id.split(':').nth(1).is_some_and(|bus| bus == "00")
+    }
+
+    /// Whether the gpu is part of the cpu package, from what the vendor ships: NVIDIA PCI
+    /// gpus are always discrete, AMD APUs are recognised by `AmdGpu::is_apu` and Intel
+    /// gpus by `IntelGpu`. The bus position is only a guess for other vendors and Intel
+    /// gpus without a driver.
+    fn vendor_is_integrated(&self) -> bool {
+        match self.vendor_id {
+            VENDOR_NVIDIA | VENDOR_AMD => false,
+            _ => self.is_on_root_bus(),
+        }
+    }
+
+    pub fn to_adapter_data<P: AsRef<Path>>(&self, root: P, adapter_index: u32) -> AdapterData {
+        let root = root.as_ref();
+        self.adapter_data(root, adapter_index, &self.driver_details(root))
+    }
+
+    /// `GPUData` with the memory usage the kernel driver reports, NVIDIA cards are left
+    /// at 0 and filled from NVML
+    pub fn to_gpu_data<P: AsRef<Path>>(&self, root: P, adapter_index: u32) -> GPUData {
+        let root = root.as_ref();
+        let details = self.driver_details(root);
+        let mut gpu = GPUData::from_adapter(&self.adapter_data(root, adapter_index, &details));
+
+        match details {
+            DriverDetails::Amd(amd) => {
+                gpu.used_memory = amd.used_memory();
+                gpu.free_memory = gpu.total_memory.saturating_sub(gpu.used_memory);
+            }
+            DriverDetails::Intel(intel) if intel.is_integrated => {
+                if let Ok(meminfo) = ProcMemInfo::from_root(root) {
+                    gpu.used_memory = meminfo.used();
+                    gpu.free_memory = meminfo.available;
+                }
+            }
+            DriverDetails::Intel(intel) => {
+                if let Some(available) = intel.lmem_available {
+                    gpu.free_memory = available;
+                    gpu.used_memory = gpu.total_memory.saturating_sub(available);
+                }
+            }
+            DriverDetails::Other => {}
+        }
+
+        gpu
+    }
+
+    /// Reads the sysfs files of the bound driver, once per device since some of them make
+    /// the driver query the hardware
+    fn driver_details(&self, root: &Path) -> DriverDetails {
+        if let Some(amd) = AmdGpu::from_device(root, self) {
+            return DriverDetails::Amd(amd);
+        }
+
+        match IntelGpu::from_device(root, self) {
+            Some(intel) => DriverDetails::Intel(intel),
+            None => DriverDetails::Other,
+        }
+    }
+
+    fn adapter_data(
+        &self,
+        root: &Path,
+        adapter_index: u32,
+        details: &DriverDetails,
+    ) -> AdapterData {
+        let mut adapter = AdapterData {
+            name: self.name.clone(),
+            vendor_id: self.vendor_id,
+            device_id: self.device_id,
+            is_hardware: true,
+            is_integrated: self.vendor_is_integrated(),
+            adapter_index,
+            driver_version: self.driver_version(root).unwrap_or_default(),
+            total_memory: 0,
+            architecture: vendor_name(self.vendor_id).to_string(),
+        };
+
+        match details {
+            DriverDetails::Amd(amd) => {
+                if let Some(product_name) = amd.product_name.clone() {
+                    adapter.name = product_name;
+                }
+                adapter.is_integrated = amd.is_apu;
+                adapter.total_memory = amd.total_memory();
+            }
+            DriverDetails::Intel(intel) => {
+                adapter.is_integrated = intel.is_integrated;
+                adapter.total_memory = match intel.lmem_total {
+                    Some(lmem) => lmem,
+                    // integrated gpus allocate from system memory
+                    None if intel.is_integrated => {
+                        ProcMemInfo::from_root(root).map(|m| m.total).unwrap_or(0)
+                    }
+                    // a discrete card on a kernel that cannot report its lmem, see
+                    // `IntelGpu::lmem_total`
+                    None => 0,
+                };
+            }
+            DriverDetails::Other => {}
+        }
+
+        adapter
+    }
+}
+
+/// What the kernel driver of a `PciGpu` reports beyond the PCI ids
+enum DriverDetails {
+    Amd(AmdGpu),
+    Intel(IntelGpu),
+    Other,
+}
+
+/// Target file name of a sysfs symlink such as `driver` or `device`
+fn link_name(path: &Path) -> Option<String> {
+    std::fs::read_link(path)
+        .ok()?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+pub(crate) fn vendor_name(vendor_id: u32) -> &'static str {
+    match vendor_id {
+        VENDOR_NVIDIA => "NVIDIA",
+        VENDOR_AMD => "AMD",
+        VENDOR_INTEL => "Intel",
+        0x1AF4 => "Virtio",
+        0x15AD => "VMware",
+        0x1234 => "QEMU",
+        0x1A03 => "ASPEED",
+        0x102B => "Matrox",
+        _ => "Unknown",
+    }
+}
+
+// the fake trees need symlinks and `:` in file names
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::gpu::{FakeGpuBackend, NvidiaGpu};
+    use crate::sysfs::FakeRoot;
+
+    /// A PCI function bound to `driver`, unbound when it is empty
+    fn add_device(root: &FakeRoot, bus_id: &str, class: &str, ids: (&str, &str), driver: &str) {
+        let dir = format!("sys/bus/pci/devices/{}", bus_id);
+        root.write(&format!("{}/class", dir), class)
+            .write(&format!("{}/vendor", dir), ids.0)
+            .write(&format!("{}/device", dir), ids.1);
+        if !driver.is_empty() {
+            root.symlink(
+                &format!("{}/driver", dir),
+                &format!("../../../../bus/pci/drivers/{}", driver),
+            );
+        }
+    }
+
+    fn add_card(root: &FakeRoot, card: &str, bus_id: &str) {
+        root.symlink(
+            &format!("sys/class/drm/{}/device", card),
+            &format!("../../../bus/pci/devices/{}", bus_id),
+        );
+    }
+
+    #[test]
+    fn lists_display_controllers() {
+        let root = FakeRoot::new("pci-read-all");
+        add_device(
+            &root,
+            "0000:00:02.0",
+            "0x030000\n",
+            ("0x8086\n", "0xa7a0\n"),
+            "i915",
+        );
+        root.write("sys/bus/pci/devices/0000:00:02.0/boot_vga", "1\n")
+            .write("sys/bus/pci/devices/0000:00:02.0/revision", "0x04\n");
+        add_device(
+            &root,
+            "0000:01:00.0",
+            "0x030200\n",
+            ("0x10de\n", "0x2684\n"),
+            "nvidia",
+        );
+        // the HD audio function next to the gpu is not a display controller
+        add_device(
+            &root,
+            "0000:00:1f.3",
+            "0x040380\n",
+            ("0x8086\n", "0x51c8\n"),
+            "snd_hda_intel",
+        );
+        add_card(&root, "card0", "0000:00:02.0");
+        add_card(&root, "card1", "0000:01:00.0");
+        root.mkdir("sys/class/drm/card0-DP-1")
+            .mkdir("sys/class/drm/card0-eDP-1")
+            .mkdir("sys/class/drm/renderD128");
+
+        let gpus = PciGpu::read_all(root.path());
+        assert_eq!(gpus.len(), 2);
+
+        let intel = &gpus[0];
+        assert_eq!(intel.bus_id, "0000:00:02.0");
+        // without pci.ids the vendor and device id stand in for the name
+        assert_eq!(intel.name, "Intel GPU a7a0");
+        assert_eq!(intel.driver.as_deref(), Some("i915"));
+        assert_eq!(intel.card, Some(0));
+        assert_eq!(intel.revision, Some(4));
+        assert!(intel.boot_vga);
+
+        let nvidia = &gpus[1];
+        assert_eq!(nvidia.name, "NVIDIA GPU 2684");
+        assert_eq!(nvidia.class, 0x030200);
+        assert_eq!(nvidia.card, Some(1));
+        assert!(!nvidia.boot_vga);
+    }
+
+    #[test]
+    fn names_devices_from_pci_ids() {
+        let pci_ids = "# comment\n\
+                       10de  NVIDIA Corporation\n\
+                       \t2684  AD102 [GeForce RTX 4090]\n\
+                       \t\t10de 165b  GeForce RTX 4090\n\
+                       10df  Emulex Corporation\n\
+                       \t2684  Not a gpu\n";
+
+        assert_eq!(
+            PciGpu::lookup_name(Some(pci_ids), 0x10de, 0x2684),
+            "NVIDIA Corporation AD102 [GeForce RTX 4090]"
+        );
+        assert_eq!(
+            PciGpu::lookup_name(Some(pci_ids), 0x10de, 0x2204),
+            "NVIDIA GPU 2204"
+        );
+        assert_eq!(PciGpu::lookup_name(None, 0x1a03, 0x2000), "ASPEED GPU 2000");
+    }
+
+    #[test]
+    fn classifies_integration_by_vendor() {
+        let gpu = |bus_id: &str, vendor_id: u32| PciGpu {
+            bus_id: bus_id.to_string(),
+            vendor_id,
+            ..Default::default()
+        };
+        let root = FakeRoot::new("pci-integrated");
+        let integrated = |gpu: PciGpu| gpu.to_adapter_data(root.path(), 0).is_integrated;
+
+        // passthrough cards on the root bus of AWS and GCP instances
+        assert!(!integrated(gpu("0000:00:1e.0", VENDOR_NVIDIA)));
+        assert!(!integrated(gpu("0000:00:04.0", VENDOR_NVIDIA)));
+        assert!(!integrated(gpu("0000:00:05.0", VENDOR_AMD)));
+        // no driver details, the bus position is all there is
+        assert!(integrated(gpu("0000:00:02.0", VENDOR_INTEL)));
+        assert!(integrated(gpu("0000:00:01.0", 0x1234)));
+        assert!(!integrated(gpu("0000:03:00.0", 0x1a03)));
+    }
+
+    #[test]
+    fn merges_backend_devices() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        let root = FakeRoot::new("pci-read-gpus");
+        add_device(
+            &root,
+            "0000:00:02.0",
+            "0x030000\n",
+            ("0x8086\n", "0xa7a0\n"),
+            "i915",
+        );
+        add_device(
+            &root,
+            "0000:01:00.0",
+            "0x030200\n",
+            ("0x10de\n", "0x27b8\n"),
+            "nvidia",
+        );
+        let nvidia = |bus_id: &str, name: &str, memory_total: u64| NvidiaGpu {
+            bus_id: bus_id.to_string(),
+            name: name.to_string(),
+            memory_total,
+            ..Default::default()
+        };
+        let backend = FakeGpuBackend::new()
+            .with_driver_version("550.54.14")
+            .with_device(nvidia("0000:01:00.0", "NVIDIA L4", 24 * GIB))
+            .with_failing_device()
+            // hidden from the fake sysfs tree
+            .with_device(nvidia("0000:41:00.0", "NVIDIA A100", 80 * GIB));
+
+        let gpus = PciGpu::read_gpus(root.path(), Some(&backend));
+        let summary: Vec<(&str, u32, bool)> = gpus
+            .iter()
+            .map(|g| (g.name.as_str(), g.adapter_index, g.is_integrated))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("NVIDIA L4", 1, false),
+                ("NVIDIA A100", 2, false),
+                ("Intel GPU a7a0", 0, true),
+            ]
+        );
+        assert_eq!(gpus[0].total_memory, 24 * GIB);
+        assert_eq!(gpus[0].driver_version.major, 550);
+
+        // without a working backend the nvidia card keeps its sysfs values
+        let unavailable = FakeGpuBackend::unavailable();
+        for gpus in [
+            PciGpu::read_gpus(root.path(), Some(&unavailable)),
+            PciGpu::read_gpus(root.path(), None),
+        ] {
+            let names: Vec<&str> = gpus.iter().map(|g| g.name.as_str()).collect();
+            assert_eq!(names, vec!["Intel GPU a7a0", "NVIDIA GPU 27b8"]);
+            assert_eq!(gpus[1].total_memory, 0);
+        }
+    }
+
+    #[test]
+    fn merges_devices_without_a_bus_id_once() {
+        let root = FakeRoot::new("pci-read-gpus-no-bus-id");
+        add_device(
+            &root,
+            "0000:01:00.0",
+            "0x030000\n",
+            ("0x10de\n", "0x2684\n"),
+            "nvidia",
+        );
+        // pci_info failed, NVML still reports the device
+        let backend = FakeGpuBackend::new().with_device(NvidiaGpu {
+            name: "NVIDIA GeForce RTX 4090".to_string(),
+            ..Default::default()
+        });
+
+        let gpus = PciGpu::read_gpus(root.path(), Some(&backend));
+        let names: Vec<&str> = gpus.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["NVIDIA GeForce RTX 4090"]);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::gpu::{AdapterData, DriverVersionData, GPUData, GPUUsage};
+use crate::gpu::{
+    AdapterData, AmdGpu, DriverVersionData, GPUData, GPUUsage, GpuBackend, IntelGpu, NvidiaGpu,
+    NvmlBackend,
+};
 use anyhow::{Result, anyhow};
 use std::ffi::c_void;
 
@@ -375,4 +378,18 @@ impl GPUUsage {
 
         Ok(adapters_list)
     }
+
+    pub fn get_amd_gpus() -> Result<Vec<AmdGpu>, Box<dyn std::error::Error>> {
+        Err("amdgpu is not available on Windows".into())
+    }
+
+    pub fn get_intel_gpus() -> Result<Vec<IntelGpu>, Box<dyn std::error::Error>> {
+        Err("i915 and xe are not available on Windows".into())
+    }
+
+    /// `adapter_index` numbers the devices in NVML order, it does not match the DXGI
+    /// adapter enumeration
+    pub fn get_nvidia_gpus() -> Result<Vec<NvidiaGpu>, Box<dyn std::error::Error>> {
+        NvmlBackend::new()?.devices(&[])
+    }
 }
@@ -1,16 +1,88 @@
 // Module: lib
+mod cgroup;
 mod cpu;
 mod gpu;
 mod memory;
 mod npu;
+mod numa;
+mod process;
 mod soc;
+mod sysfs;
+mod thermal;
 
+pub use crate::cgroup::CgroupVersion;
 pub use crate::cpu::CPUUsage as CPUStats;
 pub use crate::cpu::CPUVendor;
+pub use crate::cpu::CacheType;
+pub use crate::cpu::CoreType;
+pub use crate::cpu::CpuCache;
+pub use crate::cpu::CpuCluster;
+pub use crate::cpu::CpuCore;
+pub use crate::cpu::CpuDie;
+pub use crate::cpu::CpuFeatureLevel;
 pub use crate::cpu::CpuFeatureSet;
+pub use crate::cpu::CpuFrequency;
+pub use crate::cpu::CpuLimits;
+pub use crate::cpu::CpuPackage;
+pub use crate::cpu::CpuSampler;
+pub use crate::cpu::CpuThrottleInfo;
+pub use crate::cpu::CpuTimes;
+pub use crate::cpu::CpuTopology;
+pub use crate::cpu::CpuUsageSample;
+pub use crate::cpu::CpuUtilization;
+pub use crate::cpu::HybridTopology;
+pub use crate::cpu::Hypervisor;
+pub use crate::cpu::LoadAverage;
+pub use crate::cpu::PowerConstraint;
+pub use crate::cpu::PowerZone;
+pub use crate::cpu::ProcCpuInfo;
+pub use crate::cpu::ProcStat;
+pub use crate::cpu::ProcessorInfo;
+pub use crate::cpu::ThrottleCounters;
+pub use crate::gpu::AmdGpu;
+pub use crate::gpu::DpmLevel;
+#[cfg(feature = "test-utils")]
+pub use crate::gpu::FakeGpuBackend;
 pub use crate::gpu::GPUData;
 pub use crate::gpu::GPUUsage as GPUStats;
+pub use crate::gpu::GpuBackend;
+pub use crate::gpu::IntelDriver;
+pub use crate::gpu::IntelGpu;
+pub use crate::gpu::IntelGt;
+pub use crate::gpu::NvidiaClocks;
+pub use crate::gpu::NvidiaGpu;
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+pub use crate::gpu::NvmlBackend;
+pub use crate::gpu::PciGpu;
+pub use crate::memory::HugePageInfo;
+pub use crate::memory::HugePagePool;
+pub use crate::memory::MemoryData;
+pub use crate::memory::MemoryDevice;
+pub use crate::memory::MemoryLimits;
+pub use crate::memory::MemoryPressure;
+pub use crate::memory::MemoryPressureLevel;
+pub use crate::memory::MemoryPressureSampler;
+pub use crate::memory::MemoryType;
 pub use crate::memory::MemoryUsage as MemoryStats;
+pub use crate::memory::PressureInfo;
+pub use crate::memory::PressureStall;
+pub use crate::memory::ProcMemInfo;
+pub use crate::memory::SwapData;
+pub use crate::memory::SwapDevice;
+pub use crate::memory::ThpDefrag;
+pub use crate::memory::ThpMode;
+pub use crate::memory::VmStat;
+pub use crate::memory::ZramDevice;
+pub use crate::memory::ZswapStats;
 pub use crate::npu::NPUUsage as NPUStats;
+pub use crate::numa::NumaNode;
+pub use crate::numa::NumaTopology;
+pub use crate::numa::NumaUsage as NumaStats;
+pub use crate::process::ProcessData;
+pub use crate::process::ProcessIo;
+pub use crate::process::ProcessSampler;
+pub use crate::process::ProcessUsage as ProcessStats;
 pub use crate::soc::SocDetails;
-
+pub use crate::thermal::SensorKind;
+pub use crate::thermal::ThermalSensor;
+pub use crate::thermal::ThermalUsage as ThermalStats;
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::sysfs;
+
+/// SMBIOS memory type, serialized the way dmidecode prints it (e.g. `DDR4`, `LPDDR5`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MemoryType {
+    Dram,
+    Sdram,
+    Ddr,
+    Ddr2,
+    Ddr3,
+    Ddr4,
+    Ddr5,
+    Lpddr,
+    Lpddr2,
+    Lpddr3,
+    Lpddr4,
+    Lpddr5,
+    Hbm,
+    Hbm2,
+    Hbm3,
+    Other,
+    Unknown,
+}
+
+impl MemoryType {
+    fn from_smbios(value: u8) -> Self {
+        match value {
+            0x01 => MemoryType::Other,
+            0x03 => MemoryType::Dram,
+            0x0F => MemoryType::Sdram,
+            0x12 => MemoryType::Ddr,
+            0x13 | 0x14 => MemoryType::Ddr2,
+            0x18 => MemoryType::Ddr3,
+            0x1A => MemoryType::Ddr4,
+            0x1B => MemoryType::Lpddr,
+            0x1C => MemoryType::Lpddr2,
+            0x1D => MemoryType::Lpddr3,
+            0x1E => MemoryType::Lpddr4,
+            0x20 => MemoryType::Hbm,
+            0x21 => MemoryType::Hbm2,
+            0x22 => MemoryType::Ddr5,
+            0x23 => MemoryType::Lpddr5,
+            0x24 => MemoryType::Hbm3,
+            _ => MemoryType::Unknown,
+        }
+    }
+}
+
+/// A memory slot from an SMBIOS type 17 (Memory Device) structure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryDevice {
+    /// Slot name, e.g. `DIMM_A1` or `ChannelA-DIMM0`
+    pub locator: Option<String>,
+    pub bank_locator: Option<String>,
+    /// in bytes, 0 for an empty slot or an unknown size
+    pub size: u64,
+    pub memory_type: MemoryType,
+    /// Maximum supported speed in MT/s
+    pub speed: Option<u32>,
+    /// Speed the memory controller runs the module at, in MT/s
+    pub configured_speed: Option<u32>,
+    /// in bits, without ECC bits
+    pub data_width: Option<u16>,
+    /// in bits, including ECC bits
+    pub total_width: Option<u16>,
+    pub rank: Option<u8>,
+    pub manufacturer: Option<String>,
+    pub part_number: Option<String>,
+}
+
+impl MemoryDevice {
+    /// Reads `<root>/sys/firmware/dmi/entries/17-*/raw`, which is only readable by root
+    pub fn read_all<P: AsRef<Path>>(root: P) -> Vec<Self> {
+        sysfs::indexed_entries(root.as_ref().join("sys/firmware/dmi/entries"), "17-")
+            .into_iter()
+            .filter_map(|(_, dir)| std::fs::read(dir.join("raw")).ok())
+            .filter_map(|raw| Self::parse(&raw))
+            .collect()
+    }
+
+    /// Parses a raw type 17 structure: the formatted area followed by the string table.
+    /// Fields added in later SMBIOS versions are `None` when the structure is too short.
+    pub fn parse(raw: &[u8]) -> Option<Self> {
+        if raw.len() < 0x15 || raw[0] != 17 {
+            return None;
+        }
+
+        let length = (raw[1] as usize).min(raw.len());
+        let formatted = &raw[..length];
+        let strings: Vec<&[u8]> = raw[length..].split(|b| *b == 0).collect();
+
+        let byte = |offset: usize| formatted.get(offset).copied();
+        let word = |offset: usize| Some(u16::from_le_bytes([byte(offset)?, byte(offset + 1)?]));
+        let dword = |offset: usize| {
+            Some(u32::from_le_bytes([
+                byte(offset)?,
+                byte(offset + 1)?,
+                byte(offset + 2)?,
+                byte(offset + 3)?,
+            ]))
+        };
+        // string references are 1-based indices into the string table, 0 means none
+        let string = |offset: usize| {
+            let index = byte(offset)? as usize;
+            let value = String::from_utf8_lossy(strings.get(index.checked_sub(1)?)?)
+                .trim()
+                .to_string();
+            match value.as_str() {
+                "" | "Not Specified" | "Unknown" | "Undefined" | "NO DIMM" => None,
+                _ => Some(value),
+            }
+        };
+        let width = |offset: usize| word(offset).filter(|w| *w != 0 && *w != 0xFFFF);
+        // 0xFFFF points to the 32-bit extended field added in SMBIOS 3.3
+        let speed = |offset: usize, extended: usize| match word(offset)? {
+            0 => None,
+            0xFFFF => dword(extended).map(|s| s & 0x7FFF_FFFF).filter(|s| *s != 0),
+            speed => Some(speed as u32),
+        };
+
+        let size = match word(0x0C)? {
+            0 | 0xFFFF => 0,
+            // the real size is in the extended size field, in MB
+            0x7FFF => (dword(0x1C).unwrap_or(0) & 0x7FFF_FFFF) as u64 * 1024 * 1024,
+            size if size & 0x8000 != 0 => (size & 0x7FFF) as u64 * 1024,
+            size => size as u64 * 1024 * 1024,
+        };
+
+        Some(MemoryDevice {
+            locator: string(0x10),
+            bank_locator: string(0x11),
+            size,
+            memory_type: byte(0x12)
+                .map(MemoryType::from_smbios)
+                .unwrap_or(MemoryType::Unknown),
+            speed: speed(0x15, 0x54),
+            configured_speed: speed(0x20, 0x58),
+            data_width: width(0x0A),
+            total_width: width(0x08),
+            rank: byte(0x1B).map(|b| b & 0x0F).filter(|r| *r != 0),
+            manufacturer: string(0x17),
+            part_number: string(0x1A),
+        })
+    }
+
+    pub fn is_populated(&self) -> bool {
+        self.size > 0
+    }
+
+    /// Peak transfer rate of the module in bytes per second, from the configured speed
+    /// (or the rated speed) and the data width
+    pub fn bandwidth(&self) -> Option<u64> {
+        if !self.is_populated() {
+            return None;
+        }
+
+        let speed = self.configured_speed.or(self.speed)? as u64;
+        let width = self.data_width? as u64;
+        Some(speed * 1_000_000 * width / 8)
+    }
+
+    /// Sum of the module bandwidths, an upper bound that assumes each module sits on its
+    /// own channel
+    pub fn total_bandwidth(devices: &[MemoryDevice]) -> Option<u64> {
+        devices
+            .iter()
+            .filter_map(|device| device.bandwidth())
+            .reduce(|a, b| a + b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture_bytes, FakeRoot};
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn parse_fixture(name: &str) -> MemoryDevice {
+        MemoryDevice::parse(&fixture_bytes(&format!("dmi/{}", name))).unwrap()
+    }
+
+    #[test]
+    fn parses_ddr4() {
+        let dimm = parse_fixture("type17-ddr4.bin");
+
+        assert_eq!(dimm.locator.as_deref(), Some("DIMM_A1"));
+        assert_eq!(dimm.bank_locator.as_deref(), Some("P0 CHANNEL A"));
+        assert_eq!(dimm.size, 16 * GIB);
+        assert_eq!(dimm.memory_type, MemoryType::Ddr4);
+        assert_eq!(dimm.speed, Some(3200));
+        assert_eq!(dimm.configured_speed, Some(2933));
+        assert_eq!((dimm.total_width, dimm.data_width), (Some(72), Some(64)));
+        assert_eq!(dimm.rank, Some(2));
+        assert_eq!(dimm.manufacturer.as_deref(), Some("Samsung"));
+        assert_eq!(dimm.part_number.as_deref(), Some("M393A2K43DB3-CWE"));
+        assert_eq!(dimm.bandwidth(), Some(2933 * 1_000_000 * 8));
+    }
+
+    #[test]
+    fn parses_ddr5_with_extended_size() {
+        // 128 GB does not fit the 15 bit size field, which then reads 0x7FFF
+        let dimm = parse_fixture("type17-ddr5-extended-size.bin");
+
+        assert_eq!(dimm.size, 128 * GIB);
+        assert_eq!(dimm.memory_type, MemoryType::Ddr5);
+        assert_eq!(dimm.speed, Some(5600));
+        assert_eq!(dimm.configured_speed, Some(4800));
+        assert_eq!(dimm.total_width, Some(80));
+        assert_eq!(dimm.manufacturer.as_deref(), Some("Micron Technology"));
+    }
+
+    #[test]
+    fn parses_empty_slots() {
+        // dmidecode prints `Size: No Module Installed` for these
+        let dimm = parse_fixture("type17-empty.bin");
+
+        assert!(!dimm.is_populated());
+        assert_eq!(dimm.locator.as_deref(), Some("CPU0_DIMM_B1"));
+        assert_eq!(dimm.memory_type, MemoryType::Unknown);
+        assert_eq!(dimm.speed, None);
+        assert_eq!(dimm.data_width, None);
+        assert_eq!(dimm.manufacturer, None);
+        assert_eq!(dimm.part_number, None);
+        assert_eq!(dimm.bandwidth(), None);
+    }
+
+    #[test]
+    fn parses_sizes_in_kilobytes() {
+        let mut raw = fixture_bytes("dmi/type17-ddr4.bin");
+        // bit 15 switches the unit to KB
+        raw[0x0C..0x0E].copy_from_slice(&(0x8000u16 | 512).to_le_bytes());
+
+        assert_eq!(MemoryDevice::parse(&raw).unwrap().size, 512 * 1024);
+    }
+
+    #[test]
+    fn rejects_other_structures() {
+        let mut raw = fixture_bytes("dmi/type17-ddr4.bin");
+        raw[0] = 16;
+        assert!(MemoryDevice::parse(&raw).is_none());
+        assert!(MemoryDevice::parse(&[17, 0x28, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn reads_all_slots() {
+        let root = FakeRoot::new("dimm-entries");
+        root.write_bytes(
+            "sys/firmware/dmi/entries/17-0/raw",
+            &fixture_bytes("dmi/type17-ddr5-extended-size.bin"),
+        )
+        .write_bytes(
+            "sys/firmware/dmi/entries/17-1/raw",
+            &fixture_bytes("dmi/type17-empty.bin"),
+        )
+        .write_bytes("sys/firmware/dmi/entries/16-0/raw", &[16, 0x17]);
+
+        let dimms = MemoryDevice::read_all(root.path());
+        assert_eq!(dimms.len(), 2);
+        assert!(dimms[0].is_populated());
+        assert!(!dimms[1].is_populated());
+        assert_eq!(
+            MemoryDevice::total_bandwidth(&dimms),
+            Some(4800 * 1_000_000 * 8)
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::memory::ProcMemInfo;
+use crate::sysfs;
+
+/// A HugeTLB pool of one page size, counts are in pages
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HugePagePool {
+    /// in bytes
+    pub page_size: u64,
+    pub total: u64,
+    pub free: u64,
+    /// Promised to mappings but not faulted in yet
+    pub reserved: u64,
+    /// Allocated above `total` through overcommit
+    pub surplus: u64,
+    pub overcommit: u64,
+}
+
+impl HugePagePool {
+    /// Pages that can still be handed out to new mappings
+    pub fn available(&self) -> u64 {
+        self.free.saturating_sub(self.reserved)
+    }
+}
+
+/// Transparent huge page policy of `enabled`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThpMode {
+    Always,
+    /// Only regions marked with `madvise(MADV_HUGEPAGE)`
+    Madvise,
+    Never,
+}
+
+/// Transparent huge page policy of `defrag`, i.e. how hard a fault tries to compact memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThpDefrag {
+    Always,
+    Defer,
+    #[serde(rename = "defer+madvise")]
+    DeferMadvise,
+    Madvise,
+    Never,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HugePageInfo {
+    /// HugeTLB pools ordered by page size
+    pub pools: Vec<HugePagePool>,
+    pub thp_enabled: Option<ThpMode>,
+    pub thp_defrag: Option<ThpDefrag>,
+    /// PMD sized transparent huge page, in bytes
+    pub thp_page_size: Option<u64>,
+    /// Anonymous memory currently backed by transparent huge pages, in bytes
+    pub anon_huge_pages: Option<u64>,
+}
+
+impl HugePageInfo {
+    /// Reads `<root>/sys/kernel/mm` and `<root>/proc/meminfo`
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Self {
+        let root = root.as_ref();
+        let thp = root.join("sys/kernel/mm/transparent_hugepage");
+
+        let thp_enabled = sysfs::read_string(thp.join("enabled")).and_then(|content| {
+            match Self::selected(&content)? {
+                "always" => Some(ThpMode::Always),
+                "madvise" => Some(ThpMode::Madvise),
+                "never" => Some(ThpMode::Never),
+                _ => None,
+            }
+        });
+
+        let thp_defrag = sysfs::read_string(thp.join("defrag")).and_then(|content| {
+            match Self::selected(&content)? {
+                "always" => Some(ThpDefrag::Always),
+                "defer" => Some(ThpDefrag::Defer),
+                "defer+madvise" => Some(ThpDefrag::DeferMadvise),
+                "madvise" => Some(ThpDefrag::Madvise),
+                "never" => Some(ThpDefrag::Never),
+                _ => None,
+            }
+        });
+
+        HugePageInfo {
+            pools: Self::read_pools(root),
+            thp_enabled,
+            thp_defrag,
+            thp_page_size: sysfs::read_u64(thp.join("hpage_pmd_size")),
+            anon_huge_pages: ProcMemInfo::from_root(root)
+                .ok()
+                .map(|meminfo| meminfo.anon_huge_pages),
+        }
+    }
+
+    /// Pools are named after their page size, e.g. `hugepages-2048kB`
+    fn read_pools(root: &Path) -> Vec<HugePagePool> {
+        let entries = match std::fs::read_dir(root.join("sys/kernel/mm/hugepages")) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut pools: Vec<HugePagePool> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let page_size = entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix("hugepages-")?
+                    .strip_suffix("kB")?
+                    .parse::<u64>()
+                    .ok()?
+                    * 1024;
+
+                let dir = entry.path();
+                let count = |file: &str| sysfs::read_u64(dir.join(file)).unwrap_or(0);
+
+                Some(HugePagePool {
+                    page_size,
+                    total: count("nr_hugepages"),
+                    free: count("free_hugepages"),
+                    reserved: count("resv_hugepages"),
+                    surplus: count("surplus_hugepages"),
+                    overcommit: count("nr_overcommit_hugepages"),
+                })
+            })
+            .collect();
+
+        pools.sort_by_key(|pool| pool.page_size);
+        pools
+    }
+
+    /// The bracketed choice of a sysfs policy file, e.g. `always [madvise] never`
+    fn selected(content: &str) -> Option<&str> {
+        content
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix('[')?.strip_suffix(']'))
+    }
+
+    /// true when anonymous mappings can get transparent huge pages, with `Madvise` only
+    /// after `madvise(MADV_HUGEPAGE)`
+    pub fn thp_available(&self) -> bool {
+        matches!(self.thp_enabled, Some(ThpMode::Always | ThpMode::Madvise))
+    }
+}
@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::cgroup::{Cgroup, CgroupVersion};
+use crate::sysfs;
+
+/// Memory restrictions applied to the current process by its cgroup, in bytes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryLimits {
+    pub cgroup_version: Option<CgroupVersion>,
+    /// Hard limit, the OOM killer runs above it (v2 `memory.max`, v1 `memory.limit_in_bytes`)
+    pub max: Option<u64>,
+    /// Throttling threshold, v2 only (`memory.high`)
+    pub high: Option<u64>,
+    /// Current usage of the cgroup including page cache
+    /// (v2 `memory.current`, v1 `memory.usage_in_bytes`)
+    pub current: Option<u64>,
+    /// Page cache on the inactive list, the first to be reclaimed when the cgroup nears its
+    /// limit (`inactive_file` in v2 `memory.stat`, `total_inactive_file` in v1)
+    pub inactive_file: Option<u64>,
+}
+
+impl MemoryLimits {
+    /// Reads the memory controller of the current process's cgroup under `root`.
+    /// Limits of the ancestors are taken into account, unlimited values are `None`.
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Self {
+        let (version, dirs) = match Cgroup::current(root).and_then(|c| c.controller_dirs("memory"))
+        {
+            Some(found) => found,
+            None => return MemoryLimits::default(),
+        };
+
+        let (max, high, current, inactive_file) = match version {
+            CgroupVersion::V2 => (
+                Self::lowest(&dirs, "memory.max"),
+                Self::lowest(&dirs, "memory.high"),
+                dirs.first()
+                    .and_then(|dir| sysfs::read_u64(dir.join("memory.current"))),
+                Self::stat(&dirs, "inactive_file"),
+            ),
+            CgroupVersion::V1 => (
+                Self::lowest(&dirs, "memory.limit_in_bytes"),
+                None,
+                dirs.first()
+                    .and_then(|dir| sysfs::read_u64(dir.join("memory.usage_in_bytes"))),
+                // the `total_` values include the child cgroups like usage_in_bytes does
+                Self::stat(&dirs, "total_inactive_file"),
+            ),
+        };
+
+        MemoryLimits {
+            cgroup_version: Some(version),
+            max,
+            high,
+            current,
+            inactive_file,
+        }
+    }
+
+    /// Usage without the inactive page cache, what the kubelet calls the working set and
+    /// compares against the limit
+    pub fn working_set(&self) -> Option<u64> {
+        self.current
+            .map(|current| current.saturating_sub(self.inactive_file.unwrap_or(0)))
+    }
+
+    /// A `key value` line of the process's own `memory.stat`
+    fn stat(dirs: &[PathBuf], key: &str) -> Option<u64> {
+        let content = sysfs::read_string(dirs.first()?.join("memory.stat"))?;
+        content.lines().find_map(|line| {
+            let (name, value) = line.split_once(' ')?;
+            if name != key {
+                return None;
+            }
+            value.trim().parse().ok()
+        })
+    }
+
+    /// Smallest value of `file` in any of the directories, unlimited v2 cgroups contain
+    /// `max` which does not parse and is skipped
+    fn lowest(dirs: &[PathBuf], file: &str) -> Option<u64> {
+        dirs.iter()
+            .filter_map(|dir| sysfs::read_u64(dir.join(file)))
+            .min()
+    }
+
+    /// The lower of `max` and `high`. Values at or above `host_total` are ignored, this
+    /// also covers unlimited v1 cgroups which report a huge page aligned number.
+    pub fn limit(&self, host_total: u64) -> Option<u64> {
+        [self.max, self.high]
+            .into_iter()
+            .flatten()
+            .filter(|limit| *limit < host_total)
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn reads_v2_limits() {
+        let root = FakeRoot::new("memory-limits-v2");
+        root.write("proc/self/cgroup", "0::/kubepods/pod1\n")
+            .write("sys/fs/cgroup/cgroup.controllers", "cpu memory\n")
+            .write("sys/fs/cgroup/kubepods/memory.max", &(4 * GIB).to_string())
+            .write("sys/fs/cgroup/kubepods/pod1/memory.max", "max\n")
+            .write(
+                "sys/fs/cgroup/kubepods/pod1/memory.high",
+                &(3 * GIB).to_string(),
+            )
+            .write(
+                "sys/fs/cgroup/kubepods/pod1/memory.current",
+                &(2 * GIB).to_string(),
+            )
+            .write(
+                "sys/fs/cgroup/kubepods/pod1/memory.stat",
+                &format!(
+                    "anon 1073741824\nfile 1073741824\ninactive_file {}\n",
+                    GIB / 2
+                ),
+            );
+
+        let limits = MemoryLimits::from_root(root.path());
+        assert_eq!(limits.cgroup_version, Some(CgroupVersion::V2));
+        assert_eq!(limits.max, Some(4 * GIB));
+        assert_eq!(limits.limit(16 * GIB), Some(3 * GIB));
+        assert_eq!(limits.inactive_file, Some(GIB / 2));
+        assert_eq!(limits.working_set(), Some(3 * GIB / 2));
+    }
+
+    #[test]
+    fn reads_v1_limits() {
+        let root = FakeRoot::new("memory-limits-v1");
+        root.write("proc/self/cgroup", "4:memory:/docker/abc\n")
+            .write(
+                "sys/fs/cgroup/memory/memory.limit_in_bytes",
+                "9223372036854771712\n",
+            )
+            .write(
+                "sys/fs/cgroup/memory/docker/abc/memory.limit_in_bytes",
+                &(2 * GIB).to_string(),
+            )
+            .write(
+                "sys/fs/cgroup/memory/docker/abc/memory.usage_in_bytes",
+                &GIB.to_string(),
+            )
+            .write(
+                "sys/fs/cgroup/memory/docker/abc/memory.stat",
+                &format!("inactive_file 1024\ntotal_inactive_file {}\n", GIB / 4),
+            );
+
+        let limits = MemoryLimits::from_root(root.path());
+        assert_eq!(limits.cgroup_version, Some(CgroupVersion::V1));
+        assert_eq!(limits.limit(16 * GIB), Some(2 * GIB));
+        assert_eq!(limits.inactive_file, Some(GIB / 4));
+        assert_eq!(limits.working_set(), Some(3 * GIB / 4));
+    }
+}
@@ -1,39 +1,65 @@
-use crate::memory::{MemoryData, MemoryUsage};
-
-fn to_bytes(memory_data: &MemoryData) -> MemoryData {
-    MemoryData {
-        total: memory_data.total * 1024,
-        free: memory_data.free * 1024,
-        used: memory_data.used * 1024,
-    }
-}
+use crate::memory::{
+    HugePageInfo, MemoryData, MemoryDevice, MemoryLimits, MemoryPressure, MemoryUsage, ProcMemInfo,
+    SwapData, SwapDevice, ZramDevice, ZswapStats,
+};
 
 impl MemoryUsage {
     pub fn get_system_memory_info() -> Result<MemoryData, Box<dyn std::error::Error>> {
-        let mem_info = sys_info::mem_info()?;
-        Ok(to_bytes(&MemoryData::new_with_values(
-            mem_info.total,
-            mem_info.free,
-            mem_info.total - mem_info.free,
-        )))
+        let meminfo = ProcMemInfo::from_root("/")?;
+        Ok(
+            MemoryData::new_with_values(meminfo.total, meminfo.free, meminfo.used())
+                .with_available(meminfo.available)
+                .limited_by(&MemoryLimits::from_root("/")),
+        )
+    }
+
+    /// Full /proc/meminfo breakdown
+    pub fn get_meminfo() -> Result<ProcMemInfo, Box<dyn std::error::Error>> {
+        ProcMemInfo::from_root("/")
+    }
+
+    /// cgroup memory limits of the current process
+    pub fn get_memory_limits() -> Result<MemoryLimits, Box<dyn std::error::Error>> {
+        Ok(MemoryLimits::from_root("/"))
     }
+
+    /// PSI based pressure of the system and the process's cgroup, use
+    /// `MemoryPressureSampler` to also take paging rates into account
+    pub fn get_memory_pressure() -> Result<MemoryPressure, Box<dyn std::error::Error>> {
+        Ok(MemoryPressure::from_root("/"))
+    }
+
+    /// Memory slots from SMBIOS, reading them requires root
+    pub fn get_memory_devices() -> Result<Vec<MemoryDevice>, Box<dyn std::error::Error>> {
+        Ok(MemoryDevice::read_all("/"))
+    }
+
+    /// HugeTLB pools and transparent huge page settings
+    pub fn get_huge_pages() -> Result<HugePageInfo, Box<dyn std::error::Error>> {
+        Ok(HugePageInfo::from_root("/"))
+    }
+
     pub fn total_system_memory() -> Result<u64, Box<dyn std::error::Error>> {
-        let mem_info = sys_info::mem_info()?;
-        Ok(mem_info.total * 1024)
+        Ok(ProcMemInfo::from_root("/")?.total)
     }
 
+    /// Excludes reclaimable caches, like free(1)
     pub fn current_system_memory_usage() -> Result<u64, Box<dyn std::error::Error>> {
-        let mem_info = sys_info::mem_info()?;
-        Ok((mem_info.total - mem_info.free) * 1024) // convert to bytes
+        Ok(ProcMemInfo::from_root("/")?.used())
     }
 
     pub fn current_system_memory_free() -> Result<u64, Box<dyn std::error::Error>> {
-        let mem_info = sys_info::mem_info()?;
-        Ok((mem_info.free) * 1024) // convert to bytes
+        Ok(ProcMemInfo::from_root("/")?.free)
     }
 
-    pub fn current_system_memory_swap() -> Result<(u64, u64), Box<dyn std::error::Error>> {
-        let mem_info = sys_info::mem_info()?;
-        Ok((mem_info.swap_total * 1024, mem_info.swap_free * 1024)) // convert to bytes
+    /// Swap totals with the active swap areas, zram devices and zswap statistics
+    pub fn current_system_memory_swap() -> Result<SwapData, Box<dyn std::error::Error>> {
+        let meminfo = ProcMemInfo::from_root("/")?;
+        Ok(SwapData {
+            devices: SwapDevice::read_all("/"),
+            zram: ZramDevice::read_all("/"),
+            zswap: ZswapStats::from_root("/"),
+            ..SwapData::new_with_values(meminfo.swap_total, meminfo.swap_free, meminfo.swap_used())
+        })
     }
 }
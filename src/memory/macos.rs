@@ -1,6 +1,9 @@
 use sysinfo::{MemoryRefreshKind, RefreshKind};
 
-use crate::memory::{MemoryData, MemoryUsage};
+use crate::memory::{
+    HugePageInfo, MemoryData, MemoryDevice, MemoryLimits, MemoryPressure, MemoryUsage, ProcMemInfo,
+    SwapData,
+};
 
 impl MemoryUsage {
     pub fn get_system_memory_info() -> Result<MemoryData, Box<dyn std::error::Error>> {
@@ -8,12 +11,34 @@ impl MemoryUsage {
             RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram()),
         );
 
-        Ok(MemoryData {
-            total: system.total_memory(),
-            free: system.free_memory(),
-            used: system.used_memory(),
-        })
+        Ok(MemoryData::new_with_values(
+            system.total_memory(),
+            system.free_memory(),
+            system.used_memory(),
+        )
+        .with_available(system.available_memory()))
     }
+
+    pub fn get_meminfo() -> Result<ProcMemInfo, Box<dyn std::error::Error>> {
+        Err("/proc/meminfo is not available on macOS".into())
+    }
+
+    pub fn get_memory_limits() -> Result<MemoryLimits, Box<dyn std::error::Error>> {
+        Err("Memory limits are not implemented for macOS".into())
+    }
+
+    pub fn get_memory_pressure() -> Result<MemoryPressure, Box<dyn std::error::Error>> {
+        Err("Memory pressure is not implemented for macOS".into())
+    }
+
+    pub fn get_memory_devices() -> Result<Vec<MemoryDevice>, Box<dyn std::error::Error>> {
+        Err("Memory devices are not implemented for macOS".into())
+    }
+
+    pub fn get_huge_pages() -> Result<HugePageInfo, Box<dyn std::error::Error>> {
+        Err("Huge pages are not implemented for macOS".into())
+    }
+
     pub fn total_system_memory() -> Result<u64, Box<dyn std::error::Error>> {
         let system = sysinfo::System::new_with_specifics(
             RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram()),
@@ -38,11 +63,15 @@ impl MemoryUsage {
         Ok(system.free_memory())
     }
 
-    pub fn current_system_memory_swap() -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    pub fn current_system_memory_swap() -> Result<SwapData, Box<dyn std::error::Error>> {
         let system = sysinfo::System::new_with_specifics(
             RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_swap()),
         );
 
-        Ok((system.used_swap(), system.total_swap()))
+        Ok(SwapData::new_with_values(
+            system.total_swap(),
+            system.free_swap(),
+            system.used_swap(),
+        ))
     }
 }
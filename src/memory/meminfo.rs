@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Parsed contents of /proc/meminfo, sizes are in bytes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcMemInfo {
+    pub total: u64,
+    pub free: u64,
+    /// Estimate of the memory available for new allocations without swapping, falls back
+    /// to free + buffers + cached on kernels older than 3.14
+    pub available: u64,
+    pub buffers: u64,
+    /// Page cache, without swap cache
+    pub cached: u64,
+    pub swap_cached: u64,
+    pub active: u64,
+    pub inactive: u64,
+    /// tmpfs and shared memory (`Shmem`)
+    pub shared: u64,
+    pub slab: u64,
+    /// Reclaimable part of `slab`
+    pub slab_reclaimable: u64,
+    pub slab_unreclaimable: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    /// Memory promised to all processes (`Committed_AS`)
+    pub committed: u64,
+    pub commit_limit: u64,
+    /// Transparent huge pages backing anonymous memory
+    pub anon_huge_pages: u64,
+    /// Number of pages in the default hugetlb pool
+    pub huge_pages_total: u64,
+    pub huge_pages_free: u64,
+    pub huge_pages_reserved: u64,
+    pub huge_pages_surplus: u64,
+    pub huge_page_size: u64,
+    /// Memory consumed by hugetlb pages of all sizes
+    pub hugetlb: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    /// RAM used by the zswap pool, Linux 6.5+
+    pub zswap: Option<u64>,
+    /// Uncompressed size of the pages in the zswap pool, Linux 6.5+
+    pub zswapped: Option<u64>,
+}
+
+impl ProcMemInfo {
+    /// Reads and parses `<root>/proc/meminfo`, pass `/` for the running system
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(root.as_ref().join("proc/meminfo"))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        // `Key:   1234 kB`, the HugePages_* counters have no unit
+        let fields: HashMap<&str, u64> = content
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                let mut parts = value.split_whitespace();
+                let value = parts.next()?.parse::<u64>().ok()?;
+                let value = match parts.next() {
+                    Some("kB") => value * 1024,
+                    _ => value,
+                };
+                Some((key.trim(), value))
+            })
+            .collect();
+        let field = |key: &str| fields.get(key).copied().unwrap_or(0);
+
+        let buffers = field("Buffers");
+        let cached = field("Cached");
+        let available = fields
+            .get("MemAvailable")
+            .copied()
+            .unwrap_or_else(|| field("MemFree") + buffers + cached);
+
+        ProcMemInfo {
+            total: field("MemTotal"),
+            free: field("MemFree"),
+            available,
+            buffers,
+            cached,
+            swap_cached: field("SwapCached"),
+            active: field("Active"),
+            inactive: field("Inactive"),
+            shared: field("Shmem"),
+            slab: field("Slab"),
+            slab_reclaimable: field("SReclaimable"),
+            slab_unreclaimable: field("SUnreclaim"),
+            dirty: field("Dirty"),
+            writeback: field("Writeback"),
+            committed: field("Committed_AS"),
+            commit_limit: field("CommitLimit"),
+            anon_huge_pages: field("AnonHugePages"),
+            huge_pages_total: field("HugePages_Total"),
+            huge_pages_free: field("HugePages_Free"),
+            huge_pages_reserved: field("HugePages_Rsvd"),
+            huge_pages_surplus: field("HugePages_Surp"),
+            huge_page_size: field("Hugepagesize"),
+            hugetlb: field("Hugetlb"),
+            swap_total: field("SwapTotal"),
+            swap_free: field("SwapFree"),
+            zswap: fields.get("Zswap").copied(),
+            zswapped: fields.get("Zswapped").copied(),
+        }
+    }
+
+    /// Page cache plus reclaimable slab, free(1) adds buffers for its `buff/cache` column
+    pub fn cache(&self) -> u64 {
+        self.cached + self.slab_reclaimable
+    }
+
+    /// Used memory as computed by free(1) since procps-ng 4.0: total - available, so
+    /// reclaimable caches do not count as used
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, FakeRoot};
+
+    const KIB: u64 = 1024;
+
+    #[test]
+    fn parses_recent_kernels() {
+        let meminfo = ProcMemInfo::parse(&fixture("meminfo/linux-6.8.txt"));
+
+        assert_eq!(meminfo.total, 32594472 * KIB);
+        assert_eq!(meminfo.free, 2873044 * KIB);
+        assert_eq!(meminfo.available, 21457128 * KIB);
+        assert_eq!(meminfo.used(), (32594472 - 21457128) * KIB);
+        assert_eq!(meminfo.cache(), (17402664 + 1134004) * KIB);
+        assert_eq!(meminfo.swap_used(), (8388604 - 8121340) * KIB);
+        assert_eq!(meminfo.zswap, Some(52412 * KIB));
+        assert_eq!(meminfo.zswapped, Some(196608 * KIB));
+        // the HugePages_* counters are page counts, not sizes
+        assert_eq!(meminfo.huge_pages_total, 4);
+        assert_eq!(meminfo.huge_pages_reserved, 1);
+        assert_eq!(meminfo.huge_page_size, 2048 * KIB);
+        assert_eq!(meminfo.hugetlb, 8192 * KIB);
+    }
+
+    #[test]
+    fn estimates_available_before_linux_3_14() {
+        let meminfo = ProcMemInfo::parse(&fixture("meminfo/linux-3.2.txt"));
+
+        // MemFree + Buffers + Cached
+        assert_eq!(meminfo.available, (312756 + 184332 + 2104608) * KIB);
+        assert_eq!(meminfo.used(), (4049404 - 312756 - 184332 - 2104608) * KIB);
+        assert_eq!(meminfo.zswap, None);
+        assert_eq!(meminfo.hugetlb, 0);
+    }
+
+    #[test]
+    fn reads_from_root() {
+        let root = FakeRoot::new("meminfo");
+        root.write("proc/meminfo", &fixture("meminfo/linux-6.8.txt"));
+
+        assert_eq!(
+            ProcMemInfo::from_root(root.path()).unwrap().total,
+            32594472 * KIB
+        );
+        assert!(ProcMemInfo::from_root(root.path().join("missing")).is_err());
+    }
+}
@@ -1,5 +1,21 @@
 use serde::{Deserialize, Serialize};
 
+mod dimm;
+mod hugepages;
+mod limits;
+mod meminfo;
+mod pressure;
+mod swap;
+
+pub use dimm::{MemoryDevice, MemoryType};
+pub use hugepages::{HugePageInfo, HugePagePool, ThpDefrag, ThpMode};
+pub use limits::MemoryLimits;
+pub use meminfo::ProcMemInfo;
+pub use pressure::{
+    MemoryPressure, MemoryPressureLevel, MemoryPressureSampler, PressureInfo, PressureStall, VmStat,
+};
+pub use swap::{SwapDevice, ZramDevice, ZswapStats};
+
 #[cfg(target_os = "macos")]
 mod macos;
 
@@ -11,24 +27,123 @@ mod linux;
 
 pub struct MemoryUsage;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MemoryData {
     pub total: u64,
     pub free: u64,
     /// Used memory also includes gpu memory, in unified memory systems
     pub used: u64,
+    /// Memory that can be allocated without swapping, including reclaimable caches.
+    /// Equal to `free` when the platform does not report it.
+    pub available: u64,
+    /// Memory the process may use, the cgroup limit when one is set, otherwise `total`
+    pub effective_total: u64,
+    /// Working set of the cgroup when limited, otherwise `used`
+    pub effective_used: u64,
+    /// What is left below the effective limit, never more than the host's available memory
+    pub effective_available: u64,
 }
 
 impl MemoryData {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Effective values are set to the host values, use `limited_by` to apply a cgroup limit
+    pub fn new_with_values(total: u64, free: u64, used: u64) -> Self {
         Self {
-            total: 0,
-            free: 0,
-            used: 0,
+            total,
+            free,
+            used,
+            available: free,
+            effective_total: total,
+            effective_used: used,
+            effective_available: free,
+        }
+    }
+
+    pub fn with_available(mut self, available: u64) -> Self {
+        self.available = available;
+        self.effective_available = available;
+        self
+    }
+
+    /// Applies a cgroup limit. Usage is the cgroup's working set, its inactive page cache is
+    /// reclaimed before the limit is hit and counts as available.
+    pub fn limited_by(mut self, limits: &MemoryLimits) -> Self {
+        if let Some(limit) = limits.limit(self.total) {
+            let used = limits.working_set().unwrap_or(self.used);
+
+            self.effective_total = limit;
+            self.effective_used = used;
+            self.effective_available = limit.saturating_sub(used).min(self.available);
         }
+        self
     }
+}
+
+/// Swap space, sizes are in bytes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SwapData {
+    pub total: u64,
+    pub free: u64,
+    pub used: u64,
+    /// Active swap areas, only populated on Linux
+    pub devices: Vec<SwapDevice>,
+    /// Initialized zram devices, only populated on Linux
+    pub zram: Vec<ZramDevice>,
+    /// `None` outside of Linux or without zswap support
+    pub zswap: Option<ZswapStats>,
+}
 
+impl SwapData {
     pub fn new_with_values(total: u64, free: u64, used: u64) -> Self {
-        Self { total, free, used }
+        Self {
+            total,
+            free,
+            used,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_to_the_cgroup_working_set() {
+        let limits = MemoryLimits {
+            max: Some(4_000),
+            current: Some(3_000),
+            inactive_file: Some(1_000),
+            ..Default::default()
+        };
+
+        let memory = MemoryData::new_with_values(16_000, 6_000, 10_000)
+            .with_available(8_000)
+            .limited_by(&limits);
+        assert_eq!(memory.effective_total, 4_000);
+        assert_eq!(memory.effective_used, 2_000);
+        assert_eq!(memory.effective_available, 2_000);
+
+        // the host's available memory still caps what is left below the limit
+        let memory = MemoryData::new_with_values(16_000, 1_000, 15_000)
+            .with_available(1_500)
+            .limited_by(&limits);
+        assert_eq!(memory.effective_available, 1_500);
+    }
+
+    #[test]
+    fn ignores_limits_above_the_host() {
+        let limits = MemoryLimits {
+            max: Some(u64::MAX),
+            current: Some(3_000),
+            ..Default::default()
+        };
+
+        let memory = MemoryData::new_with_values(16_000, 6_000, 10_000).limited_by(&limits);
+        assert_eq!(memory.effective_total, 16_000);
+        assert_eq!(memory.effective_used, 10_000);
     }
 }
@@ -0,0 +1,341 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::cgroup::{Cgroup, CgroupVersion};
+
+/// Share of wall time tasks were stalled on memory, averages are in percent
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PressureStall {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+    /// Cumulative stall time in microseconds
+    pub total: u64,
+}
+
+/// Pressure stall information (PSI) of a memory pressure file
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PressureInfo {
+    /// At least one task was stalled
+    pub some: PressureStall,
+    /// All non-idle tasks were stalled at the same time
+    pub full: PressureStall,
+}
+
+impl PressureInfo {
+    /// Reads a PSI file such as /proc/pressure/memory or a cgroup's `memory.pressure`.
+    /// Fails when the kernel was built or booted without PSI.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&content))
+    }
+
+    /// Parses `some avg10=0.00 avg60=0.00 avg300=0.00 total=0` lines
+    pub fn parse(content: &str) -> Self {
+        let mut result = PressureInfo::default();
+
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let stall = match parts.next() {
+                Some("some") => &mut result.some,
+                Some("full") => &mut result.full,
+                _ => continue,
+            };
+
+            for (key, value) in parts.filter_map(|part| part.split_once('=')) {
+                match key {
+                    "avg10" => stall.avg10 = value.parse().unwrap_or(0.0),
+                    "avg60" => stall.avg60 = value.parse().unwrap_or(0.0),
+                    "avg300" => stall.avg300 = value.parse().unwrap_or(0.0),
+                    "total" => stall.total = value.parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Paging counters of /proc/vmstat
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct VmStat {
+    pub major_faults: u64,
+    /// Pages read from swap
+    pub swap_in: u64,
+    /// Pages written to swap
+    pub swap_out: u64,
+}
+
+impl VmStat {
+    /// Reads and parses `<root>/proc/vmstat`, pass `/` for the running system
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(root.as_ref().join("proc/vmstat"))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut result = VmStat::default();
+
+        for line in content.lines() {
+            let (key, value) = match line.split_once(' ') {
+                Some((key, value)) => (key, value.trim().parse::<u64>().unwrap_or(0)),
+                None => continue,
+            };
+
+            match key {
+                "pgmajfault" => result.major_faults = value,
+                "pswpin" => result.swap_in = value,
+                "pswpout" => result.swap_out = value,
+                _ => {}
+            }
+        }
+
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryPressureLevel {
+    #[default]
+    Normal,
+    /// Tasks are regularly waiting on reclaim or swap, caches should be trimmed
+    Warning,
+    /// The system is thrashing, an OOM kill is likely
+    Critical,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryPressure {
+    /// System wide PSI, `None` without PSI support
+    pub system: Option<PressureInfo>,
+    /// PSI of the process's cgroup, cgroup v2 only
+    pub cgroup: Option<PressureInfo>,
+    /// Rates since the previous sample, `None` for a single snapshot
+    pub major_faults_per_sec: Option<f64>,
+    /// in pages per second
+    pub swap_in_per_sec: Option<f64>,
+    /// in pages per second
+    pub swap_out_per_sec: Option<f64>,
+    pub level: MemoryPressureLevel,
+}
+
+impl MemoryPressure {
+    /// Reads the system and cgroup PSI under `root`, without paging rates
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Self {
+        let root = root.as_ref();
+
+        let cgroup = Cgroup::current(root)
+            .and_then(|c| c.controller_dirs("memory"))
+            .filter(|(version, _)| *version == CgroupVersion::V2)
+            .and_then(|(_, dirs)| dirs.into_iter().next())
+            .and_then(|dir| PressureInfo::from_path(dir.join("memory.pressure")).ok());
+
+        let mut pressure = MemoryPressure {
+            system: PressureInfo::from_path(root.join("proc/pressure/memory")).ok(),
+            cgroup,
+            ..Default::default()
+        };
+        pressure.level = pressure.compute_level();
+        pressure
+    }
+
+    fn with_rates(mut self, previous: &VmStat, current: &VmStat, seconds: f64) -> Self {
+        if seconds > 0.0 {
+            let rate = |prev: u64, cur: u64| Some(cur.saturating_sub(prev) as f64 / seconds);
+
+            self.major_faults_per_sec = rate(previous.major_faults, current.major_faults);
+            self.swap_in_per_sec = rate(previous.swap_in, current.swap_in);
+            self.swap_out_per_sec = rate(previous.swap_out, current.swap_out);
+            self.level = self.compute_level();
+        }
+        self
+    }
+
+    /// Worst of the system and cgroup PSI 10s averages, raised to a warning while pages
+    /// are steadily read back from swap
+    fn compute_level(&self) -> MemoryPressureLevel {
+        let psi_level = |psi: &PressureInfo| {
+            if psi.full.avg10 >= 10.0 || psi.some.avg10 >= 40.0 {
+                MemoryPressureLevel::Critical
+            } else if psi.full.avg10 >= 1.0 || psi.some.avg10 >= 10.0 {
+                MemoryPressureLevel::Warning
+            } else {
+                MemoryPressureLevel::Normal
+            }
+        };
+
+        let swapping = self.swap_in_per_sec.unwrap_or(0.0) >= 100.0
+            || self.major_faults_per_sec.unwrap_or(0.0) >= 1000.0;
+
+        self.system
+            .iter()
+            .chain(self.cgroup.iter())
+            .map(psi_level)
+            .chain(swapping.then_some(MemoryPressureLevel::Warning))
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Measures memory pressure including paging rates from the difference between two
+/// /proc/vmstat snapshots, works like `CpuSampler`
+pub struct MemoryPressureSampler {
+    root: PathBuf,
+    previous: VmStat,
+    taken: Instant,
+}
+
+impl MemoryPressureSampler {
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_root("/")
+    }
+
+    pub fn with_root<P: AsRef<Path>>(root: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let root = root.as_ref().to_path_buf();
+        let previous = VmStat::from_root(&root)?;
+        Ok(Self {
+            root,
+            previous,
+            taken: Instant::now(),
+        })
+    }
+
+    pub fn sample(&mut self) -> Result<MemoryPressure, Box<dyn std::error::Error>> {
+        let current = VmStat::from_root(&self.root)?;
+        let now = Instant::now();
+
+        let pressure = MemoryPressure::from_root(&self.root).with_rates(
+            &self.previous,
+            &current,
+            now.duration_since(self.taken).as_secs_f64(),
+        );
+
+        self.previous = current;
+        self.taken = now;
+        Ok(pressure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, FakeRoot};
+
+    fn psi(some: f32, full: f32) -> PressureInfo {
+        PressureInfo {
+            some: PressureStall {
+                avg10: some,
+                ..Default::default()
+            },
+            full: PressureStall {
+                avg10: full,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn level(system: Option<PressureInfo>, cgroup: Option<PressureInfo>) -> MemoryPressureLevel {
+        MemoryPressure {
+            system,
+            cgroup,
+            ..Default::default()
+        }
+        .compute_level()
+    }
+
+    #[test]
+    fn parses_some_and_full() {
+        let info = PressureInfo::parse(&fixture("pressure/memory.txt"));
+
+        assert_eq!(info.some.avg10, 12.5);
+        assert_eq!(info.some.avg300, 1.07);
+        assert_eq!(info.some.total, 48213377);
+        assert_eq!(info.full.avg10, 3.02);
+        assert_eq!(info.full.avg60, 0.98);
+        assert_eq!(info.full.total, 11820442);
+    }
+
+    #[test]
+    fn parses_files_without_full() {
+        // /proc/pressure/cpu has no full line before Linux 5.13
+        let info = PressureInfo::parse(&fixture("pressure/cpu-5.10.txt"));
+
+        assert_eq!(info.some.avg60, 0.51);
+        assert_eq!(info.some.total, 2389412098);
+        assert_eq!(info.full.avg10, 0.0);
+        assert_eq!(info.full.total, 0);
+    }
+
+    #[test]
+    fn parses_vmstat() {
+        let vmstat = VmStat::parse(&fixture("vmstat/linux-6.8.txt"));
+
+        assert_eq!(vmstat.major_faults, 96341);
+        assert_eq!(vmstat.swap_in, 8812);
+        assert_eq!(vmstat.swap_out, 75430);
+    }
+
+    #[test]
+    fn derives_levels_from_psi() {
+        use MemoryPressureLevel::*;
+
+        assert_eq!(level(None, None), Normal);
+        assert_eq!(level(Some(psi(9.99, 0.99)), None), Normal);
+        assert_eq!(level(Some(psi(10.0, 0.0)), None), Warning);
+        assert_eq!(level(Some(psi(0.0, 1.0)), None), Warning);
+        assert_eq!(level(Some(psi(39.99, 9.99)), None), Warning);
+        assert_eq!(level(Some(psi(40.0, 0.0)), None), Critical);
+        assert_eq!(level(Some(psi(0.0, 10.0)), None), Critical);
+        // a cgroup at its limit while the host is fine
+        assert_eq!(level(Some(psi(0.0, 0.0)), Some(psi(45.0, 12.0))), Critical);
+    }
+
+    #[test]
+    fn raises_the_level_while_swapping() {
+        let previous = VmStat {
+            major_faults: 1000,
+            swap_in: 500,
+            swap_out: 0,
+        };
+        let rates = |swap_in: u64, major_faults: u64| {
+            let current = VmStat {
+                major_faults,
+                swap_in,
+                swap_out: 0,
+            };
+            MemoryPressure::default().with_rates(&previous, &current, 2.0)
+        };
+
+        let calm = rates(600, 2000);
+        assert_eq!(calm.swap_in_per_sec, Some(50.0));
+        assert_eq!(calm.major_faults_per_sec, Some(500.0));
+        assert_eq!(calm.level, MemoryPressureLevel::Normal);
+
+        assert_eq!(rates(700, 1000).level, MemoryPressureLevel::Warning);
+        assert_eq!(rates(500, 3000).level, MemoryPressureLevel::Warning);
+        // no elapsed time, no rates
+        let instant = MemoryPressure::default().with_rates(&previous, &previous, 0.0);
+        assert_eq!(instant.swap_in_per_sec, None);
+    }
+
+    #[test]
+    fn reads_system_and_cgroup_psi() {
+        let root = FakeRoot::new("memory-pressure");
+        root.write("proc/pressure/memory", &fixture("pressure/memory.txt"))
+            .write("proc/self/cgroup", "0::/app\n")
+            .write("sys/fs/cgroup/cgroup.controllers", "memory\n")
+            .write(
+                "sys/fs/cgroup/app/memory.pressure",
+                "some avg10=45.00 avg60=30.00 avg300=10.00 total=1\n\
+                 full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n",
+            );
+
+        let pressure = MemoryPressure::from_root(root.path());
+        assert_eq!(pressure.system.unwrap().some.avg10, 12.5);
+        assert_eq!(pressure.cgroup.unwrap().some.avg10, 45.0);
+        assert_eq!(pressure.level, MemoryPressureLevel::Critical);
+    }
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::memory::ProcMemInfo;
+use crate::sysfs;
+
+/// An active swap area from /proc/swaps, sizes are in bytes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapDevice {
+    /// Device or file path, e.g. `/dev/zram0` or `/swapfile`
+    pub path: String,
+    /// `partition` or `file`
+    pub kind: String,
+    pub size: u64,
+    pub used: u64,
+    /// Higher priority areas are used first
+    pub priority: i32,
+}
+
+impl SwapDevice {
+    /// Reads and parses `<root>/proc/swaps`
+    pub fn read_all<P: AsRef<Path>>(root: P) -> Vec<Self> {
+        std::fs::read_to_string(root.as_ref().join("proc/swaps"))
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(content: &str) -> Vec<Self> {
+        // the first line is the header, sizes are in KiB
+        content
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                match parts.as_slice() {
+                    [path, kind, size, used, priority] => Some(SwapDevice {
+                        // spaces in paths are escaped as \040
+                        path: path.replace("\\040", " "),
+                        kind: kind.to_string(),
+                        size: size.parse::<u64>().ok()? * 1024,
+                        used: used.parse::<u64>().ok()? * 1024,
+                        priority: priority.parse().ok()?,
+                    }),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A compressed RAM block device, sizes are in bytes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ZramDevice {
+    /// e.g. `zram0`
+    pub name: String,
+    /// Uncompressed capacity of the device
+    pub disk_size: u64,
+    pub algorithm: Option<String>,
+    /// Data stored in the device before compression
+    pub original_size: u64,
+    pub compressed_size: u64,
+    /// RAM consumed including allocator overhead
+    pub memory_used: u64,
+    /// 0 when unlimited
+    pub memory_limit: u64,
+    /// Pages that were stored without compression because all bytes are the same
+    pub same_pages: u64,
+}
+
+impl ZramDevice {
+    /// Reads the `zramN` devices under `<root>/sys/block`, uninitialized ones are skipped
+    pub fn read_all<P: AsRef<Path>>(root: P) -> Vec<Self> {
+        sysfs::indexed_entries(root.as_ref().join("sys/block"), "zram")
+            .into_iter()
+            .filter_map(|(index, dir)| {
+                let disk_size = sysfs::read_u64(dir.join("disksize")).filter(|s| *s > 0)?;
+                let stats: Vec<u64> = sysfs::read_string(dir.join("mm_stat"))
+                    .map(|s| {
+                        s.split_whitespace()
+                            .filter_map(|v| v.parse().ok())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let stat = |i: usize| stats.get(i).copied().unwrap_or(0);
+
+                Some(ZramDevice {
+                    name: format!("zram{}", index),
+                    disk_size,
+                    // `lzo lzo-rle [zstd]`, the active one is bracketed
+                    algorithm: sysfs::read_string(dir.join("comp_algorithm")).and_then(|s| {
+                        s.split_whitespace()
+                            .find_map(|a| a.strip_prefix('[')?.strip_suffix(']'))
+                            .map(|a| a.to_string())
+                    }),
+                    original_size: stat(0),
+                    compressed_size: stat(1),
+                    memory_used: stat(2),
+                    memory_limit: stat(3),
+                    same_pages: stat(5),
+                })
+            })
+            .collect()
+    }
+
+    /// Original size over compressed size, `None` while the device is empty
+    pub fn compression_ratio(&self) -> Option<f64> {
+        (self.compressed_size > 0).then(|| self.original_size as f64 / self.compressed_size as f64)
+    }
+}
+
+/// The compressed swap cache in front of the swap devices, sizes are in bytes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ZswapStats {
+    pub enabled: bool,
+    pub compressor: Option<String>,
+    /// Maximum share of RAM the pool may use, in percent
+    pub max_pool_percent: Option<u32>,
+    /// RAM used by the compressed pool, needs Linux 6.5 or newer
+    pub pool_size: Option<u64>,
+    /// Size of the pages stored in the pool before compression, needs Linux 6.5 or newer
+    pub stored_size: Option<u64>,
+}
+
+impl ZswapStats {
+    /// Reads `<root>/sys/module/zswap/parameters` and the zswap lines of
+    /// `<root>/proc/meminfo`, `None` when the kernel has no zswap support
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Option<Self> {
+        let root = root.as_ref();
+        let parameters = root.join("sys/module/zswap/parameters");
+        if !parameters.is_dir() {
+            return None;
+        }
+
+        let meminfo = ProcMemInfo::from_root(root).ok();
+
+        Some(ZswapStats {
+            enabled: sysfs::read_string(parameters.join("enabled"))
+                .is_some_and(|v| v == "Y" || v == "1"),
+            compressor: sysfs::read_string(parameters.join("compressor")),
+            max_pool_percent: sysfs::read_u64(parameters.join("max_pool_percent"))
+                .map(|v| v as u32),
+            pool_size: meminfo.as_ref().and_then(|m| m.zswap),
+            stored_size: meminfo.as_ref().and_then(|m| m.zswapped),
+        })
+    }
+
+    /// Stored size over pool size, `None` while the pool is empty
+    pub fn compression_ratio(&self) -> Option<f64> {
+        match (self.stored_size, self.pool_size) {
+            (Some(stored), Some(pool)) if pool > 0 => Some(stored as f64 / pool as f64),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, FakeRoot};
+
+    const KIB: u64 = 1024;
+
+    #[test]
+    fn parses_swap_areas() {
+        let swaps = SwapDevice::parse(&fixture("swaps/linux-6.8.txt"));
+
+        let summary: Vec<(&str, &str, u64, u64, i32)> = swaps
+            .iter()
+            .map(|s| (s.path.as_str(), s.kind.as_str(), s.size, s.used, s.priority))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/dev/zram0", "partition", 8388604 * KIB, 524288 * KIB, 100),
+                (
+                    "/mnt/swap files/swapfile",
+                    "file",
+                    4194300 * KIB,
+                    1024 * KIB,
+                    -2
+                ),
+                ("/dev/nvme0n1p3", "partition", 16777212 * KIB, 0, -3),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_swap_areas_from_root() {
+        let root = FakeRoot::new("swaps");
+        assert!(SwapDevice::read_all(root.path()).is_empty());
+
+        // only the header without active swap
+        root.write("proc/swaps", "Filename\tType\tSize\tUsed\tPriority\n");
+        assert!(SwapDevice::read_all(root.path()).is_empty());
+    }
+
+    #[test]
+    fn reads_zram_devices() {
+        let root = FakeRoot::new("zram");
+        root.write("sys/block/zram0/disksize", "8589934592\n")
+            .write("sys/block/zram0/comp_algorithm", "lzo lzo-rle lz4 [zstd]\n")
+            // orig_data_size compr_data_size mem_used_total mem_limit mem_used_max
+            // same_pages pages_compacted huge_pages
+            .write(
+                "sys/block/zram0/mm_stat",
+                "536870912 134217728 140509184        0 150994944     2048        0     12\n",
+            )
+            // reset devices report a disksize of 0
+            .write("sys/block/zram1/disksize", "0\n");
+
+        let zram = ZramDevice::read_all(root.path());
+        assert_eq!(zram.len(), 1);
+
+        let zram0 = &zram[0];
+        assert_eq!(zram0.name, "zram0");
+        assert_eq!(zram0.disk_size, 8 * 1024 * 1024 * 1024);
+        assert_eq!(zram0.algorithm.as_deref(), Some("zstd"));
+        assert_eq!(zram0.original_size, 512 * 1024 * KIB);
+        assert_eq!(zram0.compressed_size, 128 * 1024 * KIB);
+        assert_eq!(zram0.memory_used, 140509184);
+        assert_eq!(zram0.memory_limit, 0);
+        assert_eq!(zram0.same_pages, 2048);
+        assert_eq!(zram0.compression_ratio(), Some(4.0));
+    }
+
+    #[test]
+    fn reads_zswap() {
+        let root = FakeRoot::new("zswap");
+        assert!(ZswapStats::from_root(root.path()).is_none());
+
+        root.write("sys/module/zswap/parameters/enabled", "Y\n")
+            .write("sys/module/zswap/parameters/compressor", "zstd\n")
+            .write("sys/module/zswap/parameters/max_pool_percent", "20\n")
+            .write("proc/meminfo", &fixture("meminfo/linux-6.8.txt"));
+
+        let zswap = ZswapStats::from_root(root.path()).unwrap();
+        assert!(zswap.enabled);
+        assert_eq!(zswap.compressor.as_deref(), Some("zstd"));
+        assert_eq!(zswap.max_pool_percent, Some(20));
+        assert_eq!(zswap.pool_size, Some(52412 * KIB));
+        assert_eq!(zswap.compression_ratio(), Some(196608.0 / 52412.0));
+    }
+}
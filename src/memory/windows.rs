@@ -1,22 +1,49 @@
-use crate::memory::{MemoryData, MemoryUsage};
+use crate::memory::{
+    HugePageInfo, MemoryData, MemoryDevice, MemoryLimits, MemoryPressure, MemoryUsage, ProcMemInfo,
+    SwapData,
+};
 
 fn to_bytes(memory_data: &MemoryData) -> MemoryData {
-    MemoryData {
-        total: memory_data.total * 1024,
-        free: memory_data.free * 1024,
-        used: memory_data.used * 1024,
-    }
+    MemoryData::new_with_values(
+        memory_data.total * 1024,
+        memory_data.free * 1024,
+        memory_data.used * 1024,
+    )
 }
 
 impl MemoryUsage {
     pub fn get_system_memory_info() -> Result<MemoryData, Box<dyn std::error::Error>> {
         let mem_info = sys_info::mem_info()?;
+        // sys-info leaves `avail` at 0 on Windows and reports ullAvailPhys, which already
+        // counts the standby list, as `free`
         Ok(to_bytes(&MemoryData::new_with_values(
             mem_info.total,
             mem_info.free,
             mem_info.total - mem_info.free,
-        )))
+        ))
+        .with_available(mem_info.free * 1024))
+    }
+
+    pub fn get_meminfo() -> Result<ProcMemInfo, Box<dyn std::error::Error>> {
+        Err("/proc/meminfo is not available on Windows".into())
+    }
+
+    pub fn get_memory_limits() -> Result<MemoryLimits, Box<dyn std::error::Error>> {
+        Err("Memory limits are not implemented for Windows".into())
+    }
+
+    pub fn get_memory_pressure() -> Result<MemoryPressure, Box<dyn std::error::Error>> {
+        Err("Memory pressure is not implemented for Windows".into())
+    }
+
+    pub fn get_memory_devices() -> Result<Vec<MemoryDevice>, Box<dyn std::error::Error>> {
+        Err("Memory devices are not implemented for Windows".into())
     }
+
+    pub fn get_huge_pages() -> Result<HugePageInfo, Box<dyn std::error::Error>> {
+        Err("Huge pages are not implemented for Windows".into())
+    }
+
     pub fn total_system_memory() -> Result<u64, Box<dyn std::error::Error>> {
         let mem_info = sys_info::mem_info()?;
         Ok(mem_info.total * 1024)
@@ -32,8 +59,13 @@ impl MemoryUsage {
         Ok((mem_info.free) * 1024) // convert to bytes
     }
 
-    pub fn current_system_memory_swap() -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    pub fn current_system_memory_swap() -> Result<SwapData, Box<dyn std::error::Error>> {
         let mem_info = sys_info::mem_info()?;
-        Ok((mem_info.swap_total * 1024, mem_info.swap_free * 1024)) // convert to bytes
+        // convert to bytes
+        Ok(SwapData::new_with_values(
+            mem_info.swap_total * 1024,
+            mem_info.swap_free * 1024,
+            mem_info.swap_total.saturating_sub(mem_info.swap_free) * 1024,
+        ))
     }
 }
@@ -1,19 +1,6 @@
 use crate::npu::{NPUData, NPUUsage};
-use std::process::Command;
 
 impl NPUUsage {
-    fn get_architecture() -> String {
-        // get the architecture of the device on linux
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg("uname -m")
-            .output()
-            .expect("Failed to execute command");
-
-        let name = String::from_utf8_lossy(&output.stdout);
-        name.trim().to_string()
-    }
-
     pub fn is_npu_available() -> bool {
         false
     }
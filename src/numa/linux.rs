@@ -0,0 +1,12 @@
+use crate::numa::{NumaTopology, NumaUsage};
+
+impl NumaUsage {
+    pub fn get_numa_topology() -> Result<NumaTopology, Box<dyn std::error::Error>> {
+        Ok(NumaTopology::from_root("/"))
+    }
+
+    /// NUMA node of a PCI device, `bus_id` is in the `0000:01:00.0` form
+    pub fn pci_device_node(bus_id: &str) -> Option<u32> {
+        NumaTopology::pci_device_node("/", bus_id)
+    }
+}
@@ -0,0 +1,11 @@
+use crate::numa::{NumaTopology, NumaUsage};
+
+impl NumaUsage {
+    pub fn get_numa_topology() -> Result<NumaTopology, Box<dyn std::error::Error>> {
+        Err("NUMA topology is not implemented for macOS".into())
+    }
+
+    pub fn pci_device_node(_bus_id: &str) -> Option<u32> {
+        None
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+mod topology;
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+pub struct NumaUsage;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NumaNode {
+    pub id: u32,
+    /// Logical cpus local to this node, empty for memory only nodes (e.g. CXL)
+    pub cpus: Vec<u32>,
+    /// in bytes
+    pub memory_total: Option<u64>,
+    /// in bytes
+    pub memory_free: Option<u64>,
+    /// Relative access cost to every node in `NumaTopology::nodes` order, 10 is local
+    pub distances: Vec<u32>,
+}
+
+/// NUMA nodes of the machine, a single node on UMA systems
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NumaTopology {
+    pub nodes: Vec<NumaNode>,
+}
@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use crate::numa::{NumaNode, NumaTopology};
+use crate::sysfs;
+
+impl NumaTopology {
+    /// Reads the nodes under `<root>/sys/devices/system/node`, empty when the kernel was
+    /// built without NUMA support
+    pub fn from_root<P: AsRef<Path>>(root: P) -> Self {
+        let nodes = sysfs::indexed_entries(root.as_ref().join("sys/devices/system/node"), "node")
+            .into_iter()
+            .map(|(id, dir)| {
+                let (memory_total, memory_free) = std::fs::read_to_string(dir.join("meminfo"))
+                    .map(|content| Self::parse_node_meminfo(&content))
+                    .unwrap_or_default();
+
+                NumaNode {
+                    id,
+                    cpus: sysfs::read_string(dir.join("cpulist"))
+                        .map(|list| sysfs::parse_cpu_list(&list))
+                        .unwrap_or_default(),
+                    memory_total,
+                    memory_free,
+                    distances: sysfs::read_string(dir.join("distance"))
+                        .map(|d| {
+                            d.split_whitespace()
+                                .filter_map(|v| v.parse().ok())
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        NumaTopology { nodes }
+    }
+
+    /// Parses `Node 0 MemTotal:  5734136 kB` lines into (MemTotal, MemFree) in bytes
+    fn parse_node_meminfo(content: &str) -> (Option<u64>, Option<u64>) {
+        let mut total = None;
+        let mut free = None;
+
+        for line in content.lines() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (key, value) = match parts.as_slice() {
+                ["Node", _, key, value, "kB"] => {
+                    (*key, value.parse::<u64>().ok().map(|v| v * 1024))
+                }
+                _ => continue,
+            };
+
+            match key {
+                "MemTotal:" => total = value,
+                "MemFree:" => free = value,
+                _ => {}
+            }
+        }
+
+        (total, free)
+    }
+
+    pub fn node(&self, id: u32) -> Option<&NumaNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// Distance between two nodes, 10 for local access
+    pub fn distance(&self, from: u32, to: u32) -> Option<u32> {
+        let index = self.nodes.iter().position(|node| node.id == to)?;
+        self.node(from)?.distances.get(index).copied()
+    }
+
+    pub fn node_of_cpu(&self, cpu: u32) -> Option<u32> {
+        self.nodes
+            .iter()
+            .find(|node| node.cpus.contains(&cpu))
+            .map(|node| node.id)
+    }
+
+    /// Node a PCI device such as a GPU is attached to, `bus_id` is in the
+    /// `0000:01:00.0` form. `None` when the platform does not report an affinity.
+    pub fn pci_device_node<P: AsRef<Path>>(root: P, bus_id: &str) -> Option<u32> {
+        let path = root
+            .as_ref()
+            .join("sys/bus/pci/devices")
+            .join(bus_id.to_lowercase())
+            .join("numa_node");
+
+        // -1 when the device has no affinity
+        sysfs::read_string(path)?.parse::<u32>().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    const KIB: u64 = 1024;
+
+    fn node_meminfo(node: u32, total: u64, free: u64) -> String {
+        format!(
+            "Node {node} MemTotal:       {total} kB\n\
+             Node {node} MemFree:        {free} kB\n\
+             Node {node} MemUsed:        {} kB\n\
+             Node {node} HugePages_Total:     0\n",
+            total - free
+        )
+    }
+
+    #[test]
+    fn reads_two_nodes() {
+        let root = FakeRoot::new("numa-nodes");
+        root.write(
+            "sys/devices/system/node/node0/meminfo",
+            &node_meminfo(0, 65536000, 12000000),
+        )
+        .write("sys/devices/system/node/node0/cpulist", "0-7,16-23\n")
+        .write("sys/devices/system/node/node0/distance", "10 21\n")
+        .write(
+            "sys/devices/system/node/node1/meminfo",
+            &node_meminfo(1, 65011712, 30000000),
+        )
+        .write("sys/devices/system/node/node1/cpulist", "8-15,24-31\n")
+        .write("sys/devices/system/node/node1/distance", "21 10\n")
+        .write("sys/devices/system/node/possible", "0-1\n");
+
+        let topology = NumaTopology::from_root(root.path());
+        assert_eq!(topology.nodes.len(), 2);
+
+        let node0 = topology.node(0).unwrap();
+        assert_eq!(node0.memory_total, Some(65536000 * KIB));
+        assert_eq!(node0.memory_free, Some(12000000 * KIB));
+        assert_eq!(node0.cpus.len(), 16);
+        assert_eq!(topology.node(1).unwrap().memory_free, Some(30000000 * KIB));
+
+        assert_eq!(topology.distance(0, 0), Some(10));
+        assert_eq!(topology.distance(0, 1), Some(21));
+        assert_eq!(topology.distance(1, 0), Some(21));
+        assert_eq!(topology.distance(0, 2), None);
+        assert_eq!(topology.node_of_cpu(20), Some(0));
+        assert_eq!(topology.node_of_cpu(24), Some(1));
+        assert_eq!(topology.node_of_cpu(64), None);
+    }
+
+    #[test]
+    fn ignores_malformed_meminfo_lines() {
+        let (total, free) = NumaTopology::parse_node_meminfo(
+            "Node 0 MemTotal:       1024 kB\nNode 0 MemFree: lots kB\nMemFree: 12 kB\n",
+        );
+        assert_eq!(total, Some(1024 * KIB));
+        assert_eq!(free, None);
+
+        // kernels built without NUMA have no node directories
+        let root = FakeRoot::new("numa-none");
+        assert!(NumaTopology::from_root(root.path()).nodes.is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+mod stats;
+
+pub use stats::ProcessSampler;
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+pub struct ProcessUsage;
+
+/// Resource usage of a single process, sizes are in bytes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessData {
+    pub pid: u32,
+    pub name: String,
+    /// Single letter state, e.g. `R` running, `S` sleeping, `Z` zombie
+    pub state: Option<String>,
+    pub threads: u32,
+    /// Resident set size
+    pub rss: u64,
+    /// Proportional set size, shared pages are split between the processes mapping them.
+    /// Needs Linux 4.14 and the permission to read the process's memory maps.
+    pub pss: Option<u64>,
+    pub swap: Option<u64>,
+    pub virtual_size: u64,
+    /// Cpu time spent in user mode, in seconds
+    pub user_time: f64,
+    /// Cpu time spent in the kernel, in seconds
+    pub system_time: f64,
+    /// `None` without permission to list the process's descriptors
+    pub open_fds: Option<u32>,
+    /// `None` without permission to read the process's io accounting
+    pub io: Option<ProcessIo>,
+}
+
+impl ProcessData {
+    /// Total cpu time in seconds
+    pub fn cpu_time(&self) -> f64 {
+        self.user_time + self.system_time
+    }
+}
+
+/// I/O counters of /proc/PID/io, in bytes
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ProcessIo {
+    /// Passed to read() like calls, including reads served from the page cache
+    pub read_chars: u64,
+    /// Passed to write() like calls
+    pub write_chars: u64,
+    /// Fetched from the storage layer
+    pub read_bytes: u64,
+    /// Sent to the storage layer
+    pub write_bytes: u64,
+}
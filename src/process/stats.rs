@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::process::{ProcessData, ProcessIo};
+use crate::sysfs;
+
+impl ProcessData {
+    /// Reads `<root>/proc/<pid>`, fails when the process does not exist
+    pub fn from_root<P: AsRef<Path>>(
+        root: P,
+        pid: u32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let dir = root.as_ref().join(format!("proc/{}", pid));
+
+        let status = std::fs::read_to_string(dir.join("status"))?;
+        let status = Self::parse_fields(&status);
+        let stat = std::fs::read_to_string(dir.join("stat"))
+            .ok()
+            .map(|content| StatFields::parse(&content))
+            .unwrap_or_default();
+        // page counts: size resident shared text lib data dt
+        let statm: Vec<u64> = sysfs::read_string(dir.join("statm"))
+            .map(|s| {
+                s.split_whitespace()
+                    .filter_map(|v| v.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let statm = |i: usize| statm.get(i).map(|pages| pages * sysfs::page_size());
+
+        let smaps = std::fs::read_to_string(dir.join("smaps_rollup"))
+            .ok()
+            .map(|content| Self::parse_fields(&content));
+
+        let ticks = sysfs::clock_ticks() as f64;
+
+        Ok(ProcessData {
+            pid,
+            name: status.text("Name").unwrap_or_default(),
+            state: status
+                .text("State")
+                .and_then(|s| s.split_whitespace().next().map(|s| s.to_string())),
+            threads: status
+                .number("Threads")
+                .map(|t| t as u32)
+                .unwrap_or(stat.threads),
+            // kernel threads have no Vm* lines in status
+            rss: status.bytes("VmRSS").or_else(|| statm(1)).unwrap_or(0),
+            pss: smaps.as_ref().and_then(|smaps| smaps.bytes("Pss")),
+            swap: status.bytes("VmSwap"),
+            virtual_size: status.bytes("VmSize").or_else(|| statm(0)).unwrap_or(0),
+            user_time: stat.user_ticks as f64 / ticks,
+            system_time: stat.system_ticks as f64 / ticks,
+            open_fds: std::fs::read_dir(dir.join("fd"))
+                .ok()
+                .map(|entries| entries.count() as u32),
+            io: std::fs::read_to_string(dir.join("io"))
+                .ok()
+                .map(|content| Self::parse_fields(&content))
+                .map(|io| ProcessIo {
+                    read_chars: io.number("rchar").unwrap_or(0),
+                    write_chars: io.number("wchar").unwrap_or(0),
+                    read_bytes: io.number("read_bytes").unwrap_or(0),
+                    write_bytes: io.number("write_bytes").unwrap_or(0),
+                }),
+        })
+    }
+
+    /// `Key: value` files such as status, smaps_rollup and io
+    fn parse_fields(content: &str) -> Fields {
+        Fields(
+            content
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .collect(),
+        )
+    }
+}
+
+struct Fields(HashMap<String, String>);
+
+impl Fields {
+    fn text(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+
+    fn number(&self, key: &str) -> Option<u64> {
+        self.0.get(key)?.parse().ok()
+    }
+
+    /// Values such as `1432 kB`
+    fn bytes(&self, key: &str) -> Option<u64> {
+        let value = self.0.get(key)?;
+        let kb = value.strip_suffix("kB").unwrap_or(value).trim();
+        kb.parse::<u64>().ok().map(|kb| kb * 1024)
+    }
+}
+
+/// The fields of /proc/PID/stat used here
+#[derive(Default)]
+struct StatFields {
+    user_ticks: u64,
+    system_ticks: u64,
+    threads: u32,
+}
+
+impl StatFields {
+    fn parse(content: &str) -> Self {
+        // the command name is in parentheses and may itself contain spaces and parentheses,
+        // the remaining fields start at the state after the last `)`
+        let fields: Vec<&str> = match content.rsplit_once(')') {
+            Some((_, rest)) => rest.split_whitespace().collect(),
+            None => return StatFields::default(),
+        };
+        // field numbers in proc(5) are 1-based and count pid and comm
+        let field = |number: usize| {
+            fields
+                .get(number - 3)
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+
+        StatFields {
+            user_ticks: field(14),
+            system_ticks: field(15),
+            threads: field(20) as u32,
+        }
+    }
+}
+
+/// Measures the cpu usage of a process from the difference of its cpu time between two
+/// calls, works like `CpuSampler`
+pub struct ProcessSampler {
+    root: PathBuf,
+    pid: u32,
+    previous: f64,
+    taken: Instant,
+}
+
+impl ProcessSampler {
+    pub fn new(pid: u32) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_root("/", pid)
+    }
+
+    pub fn with_root<P: AsRef<Path>>(
+        root: P,
+        pid: u32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let root = root.as_ref().to_path_buf();
+        let previous = ProcessData::from_root(&root, pid)?.cpu_time();
+        Ok(Self {
+            root,
+            pid,
+            previous,
+            taken: Instant::now(),
+        })
+    }
+
+    /// Cpu usage since the previous call in percent of one cpu, a process keeping four
+    /// cpus busy reports 400
+    pub fn sample(&mut self) -> Result<f32, Box<dyn std::error::Error>> {
+        let current = ProcessData::from_root(&self.root, self.pid)?.cpu_time();
+        let now = Instant::now();
+
+        let elapsed = now.duration_since(self.taken).as_secs_f64();
+        let usage = if elapsed > 0.0 {
+            ((current - self.previous).max(0.0) / elapsed * 100.0) as f32
+        } else {
+            0.0
+        };
+
+        self.previous = current;
+        self.taken = now;
+        Ok(usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, FakeRoot};
+
+    #[test]
+    fn splits_stat_after_the_last_parenthesis() {
+        let stat = StatFields::parse(&fixture("proc-pid/stat.txt"));
+
+        assert_eq!(stat.user_ticks, 1234);
+        assert_eq!(stat.system_ticks, 567);
+        assert_eq!(stat.threads, 7);
+
+        let empty = StatFields::parse("4242 no comm");
+        assert_eq!((empty.user_ticks, empty.threads), (0, 0));
+    }
+
+    #[test]
+    fn converts_kilobytes() {
+        let fields =
+            ProcessData::parse_fields("VmRSS:\t   10240 kB\nThreads:\t7\nPss:  88 kB\nBad: x kB\n");
+
+        assert_eq!(fields.bytes("VmRSS"), Some(10240 * 1024));
+        assert_eq!(fields.bytes("Pss"), Some(88 * 1024));
+        assert_eq!(fields.bytes("Bad"), None);
+        assert_eq!(fields.bytes("VmSwap"), None);
+        assert_eq!(fields.number("Threads"), Some(7));
+    }
+
+    #[test]
+    fn reads_a_process() {
+        let root = FakeRoot::new("process");
+        root.write("proc/4242/status", &fixture("proc-pid/status.txt"))
+            .write("proc/4242/stat", &fixture("proc-pid/stat.txt"))
+            .write("proc/4242/io", &fixture("proc-pid/io.txt"))
+            .write("proc/4242/smaps_rollup", "Rss:  10240 kB\nPss:   8192 kB\n")
+            .write("proc/4242/fd/0", "")
+            .write("proc/4242/fd/1", "");
+
+        let process = ProcessData::from_root(root.path(), 4242).unwrap();
+        assert_eq!(process.name, "my) (app x");
+        assert_eq!(process.state.as_deref(), Some("S"));
+        assert_eq!(process.threads, 7);
+        assert_eq!(process.rss, 10240 * 1024);
+        assert_eq!(process.pss, Some(8192 * 1024));
+        assert_eq!(process.swap, Some(512 * 1024));
+        assert_eq!(process.virtual_size, 102400 * 1024);
+        let ticks = sysfs::clock_ticks() as f64;
+        assert_eq!(process.user_time, 1234.0 / ticks);
+        assert_eq!(process.system_time, 567.0 / ticks);
+        assert_eq!(process.open_fds, Some(2));
+        let io = process.io.unwrap();
+        assert_eq!((io.read_chars, io.write_bytes), (8421376, 524288));
+
+        assert!(ProcessData::from_root(root.path(), 1).is_err());
+    }
+
+    #[test]
+    fn falls_back_to_statm_for_kernel_threads() {
+        let root = FakeRoot::new("process-kthread");
+        root.write("proc/2/status", "Name:\tkthreadd\nState:\tS (sleeping)\n")
+            .write("proc/2/statm", "0 0 0 0 0 0 0\n");
+
+        let process = ProcessData::from_root(root.path(), 2).unwrap();
+        assert_eq!(process.rss, 0);
+        assert_eq!(process.swap, None);
+        assert_eq!(process.open_fds, None);
+        assert!(process.io.is_none());
+    }
+}
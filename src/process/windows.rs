@@ -0,0 +1,11 @@
+use crate::process::{ProcessData, ProcessUsage};
+
+impl ProcessUsage {
+    pub fn get_process_info(_pid: u32) -> Result<ProcessData, Box<dyn std::error::Error>> {
+        Err("Process stats are not implemented for Windows".into())
+    }
+
+    pub fn get_current_process_info() -> Result<ProcessData, Box<dyn std::error::Error>> {
+        Self::get_process_info(std::process::id())
+    }
+}
@@ -1,28 +1,61 @@
-use crate::soc::Soc;
+use crate::cpu::{CpuCache, HybridTopology, ProcCpuInfo};
+use crate::soc::{Core, CoreConfig, CpuInfo, Soc};
 use crate::SocDetails;
-use std::process::Command;
 
 impl SocDetails {
     pub fn get_current_soc_info() -> Soc {
-        // parse the soc.json file and return the Soc struct
+        // the bundled soc database only covers apple silicon, so the cpu details
+        // are derived from procfs / sysfs at runtime
+        let cpuinfo = ProcCpuInfo::from_root("/").unwrap_or_default();
+        let hybrid = HybridTopology::detect("/");
+        let caches = CpuCache::read_all("/");
 
-        // let s = include_str!("db/apple/soc.json");
-        // let soc_details: Result<SocCollection, Error> = serde_json::from_str(s);
-        // let (name, cc) = Self::get_name_and_core_count();
+        // caches seen by the first cpu of each core type
+        let caches_of = |cpus: &[u32]| -> Vec<CpuCache> {
+            cpus.first()
+                .map(|cpu| {
+                    caches
+                        .iter()
+                        .filter(|c| c.shared_cpus.contains(cpu))
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
 
-        Soc::new(None, None, None, 0, None, None, None, None, 0)
-    }
-
-    fn get_name_and_core_count() -> (String, u32) {
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg("cat /proc/cpuinfo | grep 'model name' | uniq")
-            .output()
-            .expect("Failed to execute command");
+        let cpu = CpuInfo {
+            heterogeneous: hybrid.is_heterogeneous(),
+            num_of_cores: hybrid.p_cores + hybrid.e_cores,
+            core_config: CoreConfig {
+                p: hybrid.p_cores,
+                e: hybrid.e_cores,
+            },
+            p_core_data: Core {
+                name: None,
+                architecture: None,
+                frequency: hybrid.p_max_frequency.unwrap_or(0),
+                cache: caches_of(&hybrid.p_cpus),
+            },
+            e_core_data: Core {
+                name: None,
+                architecture: None,
+                frequency: hybrid.e_max_frequency.unwrap_or(0),
+                cache: caches_of(&hybrid.e_cpus),
+            },
+        };
 
-        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        let core_count = sys_info::cpu_num().unwrap();
+        let vendor = cpuinfo.processors.first().and_then(|p| p.vendor_id.clone());
 
-        (name.to_string(), core_count)
+        Soc::new(
+            cpuinfo.model_name(),
+            vendor,
+            None,
+            0,
+            Some(cpu),
+            None,
+            None,
+            None,
+            0,
+        )
     }
 }
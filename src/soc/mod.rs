@@ -7,14 +7,15 @@ mod windows;
 #[cfg(target_os = "linux")]
 mod linux;
 
+use crate::cpu::{CoreType, CpuCache};
 use serde::{Deserialize, Serialize};
 
 pub struct SocDetails;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CoreConfig {
-    p: u32,
-    e: u32,
+    pub p: u32,
+    pub e: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -22,7 +23,8 @@ pub struct Core {
     name: Option<String>,
     architecture: Option<String>,
     frequency: u32,
-    // cache: Vec<Cache>,
+    #[serde(default)]
+    cache: Vec<CpuCache>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -75,9 +77,11 @@ pub struct Soc {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
 pub struct SocCollection(Vec<Soc>);
 
 impl Soc {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         name: Option<String>,
         vendor: Option<String>,
@@ -144,6 +148,28 @@ impl Soc {
         self.cpu.as_ref().unwrap().e_core_data.clone()
     }
 
+    /// Caches of both core types, each tagged with the core type it belongs to. Empty if the
+    /// soc has no cache data.
+    pub fn caches(&self) -> Vec<CpuCache> {
+        let Some(cpu) = self.cpu.as_ref() else {
+            return Vec::new();
+        };
+
+        let tagged = |core: &Core, core_type: CoreType| {
+            core.cache
+                .iter()
+                .map(move |cache| CpuCache {
+                    core_type: Some(core_type),
+                    ..cache.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut caches = tagged(&cpu.p_core_data, CoreType::Performance);
+        caches.extend(tagged(&cpu.e_core_data, CoreType::Efficiency));
+        caches
+    }
+
     pub fn gpu_name(&self) -> Option<String> {
         self.gpu.as_ref().unwrap().name.clone()
     }
@@ -198,3 +224,58 @@ impl Soc {
         self.data_rate
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::CacheType;
+
+    fn l2(size: u64) -> CpuCache {
+        CpuCache {
+            level: 2,
+            cache_type: CacheType::Unified,
+            size,
+            line_size: Some(128),
+            ways_of_associativity: None,
+            shared_cpus: Vec::new(),
+            core_type: None,
+        }
+    }
+
+    fn core(cache: Vec<CpuCache>) -> Core {
+        Core {
+            name: None,
+            architecture: None,
+            frequency: 0,
+            cache,
+        }
+    }
+
+    #[test]
+    fn tags_caches_with_their_core_type() {
+        let cpu = CpuInfo {
+            heterogeneous: true,
+            num_of_cores: 8,
+            core_config: CoreConfig { p: 4, e: 4 },
+            p_core_data: core(vec![l2(16 * 1024 * 1024)]),
+            e_core_data: core(vec![l2(4 * 1024 * 1024)]),
+        };
+        let soc = Soc::new(None, None, None, 0, Some(cpu), None, None, None, 0);
+
+        let caches: Vec<(u64, Option<CoreType>)> = soc
+            .caches()
+            .iter()
+            .map(|cache| (cache.size, cache.core_type))
+            .collect();
+        assert_eq!(
+            caches,
+            vec![
+                (16 * 1024 * 1024, Some(CoreType::Performance)),
+                (4 * 1024 * 1024, Some(CoreType::Efficiency)),
+            ]
+        );
+
+        let empty = Soc::new(None, None, None, 0, None, None, None, None, 0);
+        assert!(empty.caches().is_empty());
+    }
+}
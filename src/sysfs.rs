@@ -0,0 +1,236 @@
+// Helpers for reading procfs / sysfs style files, paths are always resolved against a
+// configurable root so the readers can run against a captured copy of the tree
+
+use std::path::{Path, PathBuf};
+
+/// Reads a file and returns its trimmed content, `None` if missing or empty
+pub(crate) fn read_string<P: AsRef<Path>>(path: P) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+pub(crate) fn read_u64<P: AsRef<Path>>(path: P) -> Option<u64> {
+    read_string(path).and_then(|s| s.parse::<u64>().ok())
+}
+
+/// Reads `0x` prefixed hexadecimal values such as PCI ids
+pub(crate) fn read_hex<P: AsRef<Path>>(path: P) -> Option<u32> {
+    let value = read_string(path)?;
+    u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+pub(crate) fn read_bool<P: AsRef<Path>>(path: P) -> Option<bool> {
+    read_u64(path).map(|v| v != 0)
+}
+
+/// Parses a kernel cpu list such as `0-3,8,10-11`
+pub(crate) fn parse_cpu_list(list: &str) -> Vec<u32> {
+    let mut cpus = Vec::new();
+
+    for range in list.trim().split(',').filter(|r| !r.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) {
+                    cpus.extend(start..=end);
+                }
+            }
+            None => {
+                if let Ok(cpu) = range.parse::<u32>() {
+                    cpus.push(cpu);
+                }
+            }
+        }
+    }
+
+    cpus
+}
+
+/// Entries of `dir` named `<prefix>N`, e.g. `hwmon3` or `cpu12`, ordered by N
+pub(crate) fn indexed_entries<P: AsRef<Path>>(dir: P, prefix: &str) -> Vec<(u32, PathBuf)> {
+    let mut entries: Vec<(u32, PathBuf)> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let index = entry
+                        .file_name()
+                        .to_str()?
+                        .strip_prefix(prefix)?
+                        .parse::<u32>()
+                        .ok()?;
+                    Some((index, entry.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    entries.sort_by_key(|(index, _)| *index);
+    entries
+}
+
+/// Indices of the files in `dir` named `<prefix>N<suffix>`, e.g. `temp12_input`, ordered by N
+pub(crate) fn indexed_files<P: AsRef<Path>>(dir: P, prefix: &str, suffix: &str) -> Vec<u32> {
+    let mut indices: Vec<u32> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()?
+                        .strip_prefix(prefix)?
+                        .strip_suffix(suffix)?
+                        .parse::<u32>()
+                        .ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    indices.sort_unstable();
+    indices
+}
+
+/// Lists the `cpuN` directories under `<root>/sys/devices/system/cpu`, sorted by index
+pub(crate) fn list_cpus<P: AsRef<Path>>(root: P) -> Vec<u32> {
+    indexed_entries(root.as_ref().join("sys/devices/system/cpu"), "cpu")
+        .into_iter()
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Logical cpus the kernel has online, all present cpus if the list is missing
+pub(crate) fn online_cpus<P: AsRef<Path>>(root: P) -> Vec<u32> {
+    let root = root.as_ref();
+    read_string(root.join("sys/devices/system/cpu/online"))
+        .map(|list| parse_cpu_list(&list))
+        .unwrap_or_else(|| list_cpus(root))
+}
+
+/// Size of a memory page, used to convert page counts such as /proc/PID/statm
+pub(crate) fn page_size() -> u64 {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: sysconf has no preconditions
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as u64;
+        }
+    }
+    4096
+}
+
+/// Clock ticks per second (USER_HZ), the unit of the cpu times in /proc
+pub(crate) fn clock_ticks() -> u64 {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: sysconf has no preconditions
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks > 0 {
+            return ticks as u64;
+        }
+    }
+    100
+}
+
+/// Path of a file under `tests/fixtures`
+#[cfg(test)]
+fn fixture_path(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path)
+}
+
+/// Contents of a text file under `tests/fixtures`
+#[cfg(test)]
+pub(crate) fn fixture(path: &str) -> String {
+    let path = fixture_path(path);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+/// Contents of a binary file under `tests/fixtures`
+#[cfg(test)]
+pub(crate) fn fixture_bytes(path: &str) -> Vec<u8> {
+    let path = fixture_path(path);
+    std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+/// A throwaway root directory the readers can be pointed at, removed when dropped
+#[cfg(test)]
+pub(crate) struct FakeRoot(PathBuf);
+
+#[cfg(test)]
+impl FakeRoot {
+    pub(crate) fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("sys-stats-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        FakeRoot(dir)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Writes `content` to `path` relative to the root, creating the parent directories
+    pub(crate) fn write(&self, path: &str, content: &str) -> &Self {
+        self.write_bytes(path, content.as_bytes())
+    }
+
+    pub(crate) fn write_bytes(&self, path: &str, content: &[u8]) -> &Self {
+        let path = self.0.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+        self
+    }
+
+    pub(crate) fn mkdir(&self, path: &str) -> &Self {
+        std::fs::create_dir_all(self.0.join(path)).unwrap();
+        self
+    }
+
+    /// Creates the symlink `path` pointing at `target`, which is taken as is
+    #[cfg(unix)]
+    pub(crate) fn symlink(&self, path: &str, target: &str) -> &Self {
+        let path = self.0.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::os::unix::fs::symlink(target, path).unwrap();
+        self
+    }
+}
+
+#[cfg(test)]
+impl Drop for FakeRoot {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list(""), Vec::<u32>::new());
+        assert_eq!(parse_cpu_list("5"), vec![5]);
+    }
+
+    #[test]
+    fn orders_indexed_entries_numerically() {
+        let root = FakeRoot::new("indexed-entries");
+        root.mkdir("hwmon10")
+            .mkdir("hwmon2")
+            .mkdir("hwmonx")
+            .mkdir("other1");
+
+        let indices: Vec<u32> = indexed_entries(root.path(), "hwmon")
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(indices, vec![2, 10]);
+    }
+}
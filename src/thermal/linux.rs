@@ -0,0 +1,23 @@
+use crate::thermal::{SensorKind, ThermalSensor, ThermalUsage};
+
+impl ThermalUsage {
+    pub fn get_sensors() -> Result<Vec<ThermalSensor>, Box<dyn std::error::Error>> {
+        Ok(ThermalSensor::read_all("/"))
+    }
+
+    /// Hottest cpu package sensor, falls back to the hottest CCD and then core
+    pub fn cpu_temperature() -> Option<f32> {
+        let sensors = ThermalSensor::read_all("/");
+        let hottest = |kind: SensorKind| {
+            sensors
+                .iter()
+                .filter(|s| s.kind == kind)
+                .map(|s| s.current)
+                .reduce(f32::max)
+        };
+
+        hottest(SensorKind::CpuPackage)
+            .or_else(|| hottest(SensorKind::CpuCcd))
+            .or_else(|| hottest(SensorKind::CpuCore))
+    }
+}
@@ -0,0 +1,11 @@
+use crate::thermal::{ThermalSensor, ThermalUsage};
+
+impl ThermalUsage {
+    pub fn get_sensors() -> Result<Vec<ThermalSensor>, Box<dyn std::error::Error>> {
+        Err("Thermal sensors are not implemented for macOS".into())
+    }
+
+    pub fn cpu_temperature() -> Option<f32> {
+        None
+    }
+}
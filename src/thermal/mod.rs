@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+mod sensors;
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+pub struct ThermalUsage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    CpuPackage,
+    /// A core complex die of an AMD cpu, covering several cores
+    CpuCcd,
+    CpuCore,
+    Gpu,
+    Nvme,
+    AcpiZone,
+    Other,
+}
+
+/// A temperature sensor, all temperatures are in degrees Celsius
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThermalSensor {
+    /// Driver or zone type, e.g. `coretemp`, `k10temp`, `amdgpu`, `acpitz`
+    pub name: String,
+    /// Sensor label, e.g. `Package id 0`, `Tctl`, `edge`
+    pub label: Option<String>,
+    pub kind: SensorKind,
+    pub current: f32,
+    /// Temperature at which the hardware starts to throttle
+    pub max: Option<f32>,
+    /// Temperature at which the hardware shuts down
+    pub critical: Option<f32>,
+}
+
+impl ThermalSensor {
+    /// Degrees left before the sensor reaches its throttle (or else critical) limit
+    pub fn headroom(&self) -> Option<f32> {
+        self.max.or(self.critical).map(|limit| limit - self.current)
+    }
+}
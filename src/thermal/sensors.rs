@@ -0,0 +1,241 @@
+use std::path::Path;
+
+use super::{SensorKind, ThermalSensor};
+use crate::sysfs;
+
+impl ThermalSensor {
+    /// Reads the sensors under `<root>/sys/class/hwmon` and `<root>/sys/class/thermal`.
+    ///
+    /// Thermal zones that are also registered as hwmon devices are only reported once.
+    pub fn read_all<P: AsRef<Path>>(root: P) -> Vec<ThermalSensor> {
+        let root = root.as_ref();
+        let mut sensors = Self::read_hwmon(root);
+        // the thermal core names the hwmon device of a zone after its type with `-` replaced
+        // by `_`, zones of the same type share one device
+        let hwmon_names: Vec<String> = sensors.iter().map(|s| s.name.replace('-', "_")).collect();
+
+        for zone in Self::read_thermal_zones(root) {
+            if !hwmon_names.contains(&zone.name.replace('-', "_")) {
+                sensors.push(zone);
+            }
+        }
+
+        sensors
+    }
+
+    fn read_hwmon(root: &Path) -> Vec<ThermalSensor> {
+        let mut sensors = Vec::new();
+
+        for (_, dir) in sysfs::indexed_entries(root.join("sys/class/hwmon"), "hwmon") {
+            let name = match sysfs::read_string(dir.join("name")) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            // channels are not contiguous, coretemp numbers them after the core id
+            for index in sysfs::indexed_files(&dir, "temp", "_input") {
+                let file = |suffix: &str| dir.join(format!("temp{}_{}", index, suffix));
+                let celsius =
+                    |suffix: &str| sysfs::read_string(file(suffix)).and_then(|v| millidegrees(&v));
+
+                let current = match celsius("input") {
+                    Some(current) => current,
+                    None => continue,
+                };
+                let label = sysfs::read_string(file("label"));
+
+                sensors.push(ThermalSensor {
+                    kind: hwmon_kind(&name, label.as_deref()),
+                    name: name.clone(),
+                    label,
+                    current,
+                    max: celsius("max"),
+                    critical: celsius("crit"),
+                });
+            }
+        }
+
+        sensors
+    }
+
+    fn read_thermal_zones(root: &Path) -> Vec<ThermalSensor> {
+        let mut sensors = Vec::new();
+
+        for (_, dir) in sysfs::indexed_entries(root.join("sys/class/thermal"), "thermal_zone") {
+            let name = match sysfs::read_string(dir.join("type")) {
+                Some(name) => name,
+                None => continue,
+            };
+            let current = match sysfs::read_string(dir.join("temp")).and_then(|v| millidegrees(&v))
+            {
+                Some(current) => current,
+                None => continue,
+            };
+
+            let mut hot = None;
+            let mut passive = None;
+            let mut critical = None;
+            for index in 0..16 {
+                let trip_type = sysfs::read_string(dir.join(format!("trip_point_{}_type", index)));
+                let trip_temp = sysfs::read_string(dir.join(format!("trip_point_{}_temp", index)))
+                    .and_then(|v| millidegrees(&v));
+
+                match (trip_type.as_deref(), trip_temp) {
+                    (Some("critical"), Some(temp)) => critical = Some(temp),
+                    (Some("hot"), Some(temp)) => hot = Some(temp),
+                    (Some("passive"), Some(temp)) => passive = passive.or(Some(temp)),
+                    _ => {}
+                }
+            }
+
+            sensors.push(ThermalSensor {
+                kind: zone_kind(&name),
+                name,
+                label: None,
+                current,
+                max: passive.or(hot),
+                critical,
+            });
+        }
+
+        sensors
+    }
+}
+
+fn hwmon_kind(name: &str, label: Option<&str>) -> SensorKind {
+    let label = label.unwrap_or_default();
+
+    match name {
+        "coretemp" if label.starts_with("Core") => SensorKind::CpuCore,
+        "coretemp" => SensorKind::CpuPackage,
+        // Tctl / Tdie describe the whole package, TccdN a core complex die
+        "k10temp" | "zenpower" if label.starts_with("Tccd") => SensorKind::CpuCcd,
+        "k10temp" | "zenpower" | "cpu_thermal" | "cpu-thermal" => SensorKind::CpuPackage,
+        "amdgpu" | "radeon" | "nouveau" | "i915" | "xe" => SensorKind::Gpu,
+        "nvme" => SensorKind::Nvme,
+        "acpitz" => SensorKind::AcpiZone,
+        _ => SensorKind::Other,
+    }
+}
+
+fn zone_kind(zone_type: &str) -> SensorKind {
+    match zone_type {
+        "x86_pkg_temp" => SensorKind::CpuPackage,
+        "acpitz" => SensorKind::AcpiZone,
+        t if t.starts_with("cpu") || t.starts_with("soc") => SensorKind::CpuPackage,
+        t if t.starts_with("gpu") => SensorKind::Gpu,
+        _ => SensorKind::Other,
+    }
+}
+
+fn millidegrees(value: &str) -> Option<f32> {
+    value.parse::<i64>().ok().map(|v| v as f32 / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeRoot;
+
+    #[test]
+    fn reads_sparse_coretemp_channels() {
+        // coretemp numbers its channels core id + 2, so big parts go past temp64
+        let root = FakeRoot::new("sensors-coretemp");
+        root.write("sys/class/hwmon/hwmon1/name", "coretemp\n")
+            .write("sys/class/hwmon/hwmon1/temp1_input", "61000\n")
+            .write("sys/class/hwmon/hwmon1/temp1_label", "Package id 0\n")
+            .write("sys/class/hwmon/hwmon1/temp1_max", "95000\n")
+            .write("sys/class/hwmon/hwmon1/temp1_crit", "105000\n")
+            .write("sys/class/hwmon/hwmon1/temp2_input", "58000\n")
+            .write("sys/class/hwmon/hwmon1/temp2_label", "Core 0\n")
+            .write("sys/class/hwmon/hwmon1/temp90_input", "63000\n")
+            .write("sys/class/hwmon/hwmon1/temp90_label", "Core 88\n");
+
+        let sensors = ThermalSensor::read_all(root.path());
+        let labels: Vec<&str> = sensors.iter().filter_map(|s| s.label.as_deref()).collect();
+        assert_eq!(labels, vec!["Package id 0", "Core 0", "Core 88"]);
+
+        assert_eq!(sensors[0].kind, SensorKind::CpuPackage);
+        assert_eq!(sensors[0].current, 61.0);
+        assert_eq!(sensors[0].headroom(), Some(34.0));
+        assert_eq!(sensors[2].kind, SensorKind::CpuCore);
+    }
+
+    #[test]
+    fn reports_k10temp_ccds() {
+        let root = FakeRoot::new("sensors-k10temp");
+        root.write("sys/class/hwmon/hwmon0/name", "k10temp\n")
+            .write("sys/class/hwmon/hwmon0/temp1_input", "54250\n")
+            .write("sys/class/hwmon/hwmon0/temp1_label", "Tctl\n")
+            .write("sys/class/hwmon/hwmon0/temp3_input", "47500\n")
+            .write("sys/class/hwmon/hwmon0/temp3_label", "Tccd1\n");
+
+        let kinds: Vec<SensorKind> = ThermalSensor::read_all(root.path())
+            .iter()
+            .map(|s| s.kind)
+            .collect();
+        assert_eq!(kinds, vec![SensorKind::CpuPackage, SensorKind::CpuCcd]);
+    }
+
+    #[test]
+    fn reports_zones_once() {
+        let root = FakeRoot::new("sensors-zones");
+        root.write("sys/class/hwmon/hwmon0/name", "acpitz\n")
+            .write("sys/class/hwmon/hwmon0/temp1_input", "27800\n")
+            .write("sys/class/thermal/thermal_zone0/type", "acpitz\n")
+            .write("sys/class/thermal/thermal_zone0/temp", "27800\n")
+            .write("sys/class/thermal/thermal_zone1/type", "x86_pkg_temp\n")
+            .write("sys/class/thermal/thermal_zone1/temp", "60000\n")
+            .write(
+                "sys/class/thermal/thermal_zone1/trip_point_0_type",
+                "passive\n",
+            )
+            .write(
+                "sys/class/thermal/thermal_zone1/trip_point_0_temp",
+                "90000\n",
+            )
+            .write(
+                "sys/class/thermal/thermal_zone1/trip_point_1_type",
+                "critical\n",
+            )
+            .write(
+                "sys/class/thermal/thermal_zone1/trip_point_1_temp",
+                "100000\n",
+            );
+
+        let sensors = ThermalSensor::read_all(root.path());
+        assert_eq!(sensors.len(), 2);
+        assert_eq!(sensors[0].kind, SensorKind::AcpiZone);
+        assert_eq!(sensors[1].kind, SensorKind::CpuPackage);
+        assert_eq!(sensors[1].max, Some(90.0));
+        assert_eq!(sensors[1].critical, Some(100.0));
+    }
+
+    #[test]
+    fn keeps_repeated_zone_types() {
+        let root = FakeRoot::new("sensors-repeated-zones");
+        // one x86_pkg_temp zone per socket, neither registers a hwmon device
+        root.write("sys/class/thermal/thermal_zone0/type", "x86_pkg_temp\n")
+            .write("sys/class/thermal/thermal_zone0/temp", "55000\n")
+            .write("sys/class/thermal/thermal_zone1/type", "x86_pkg_temp\n")
+            .write("sys/class/thermal/thermal_zone1/temp", "61000\n")
+            // the hwmon device of the cpu-thermal zone is named cpu_thermal
+            .write("sys/class/thermal/thermal_zone2/type", "cpu-thermal\n")
+            .write("sys/class/thermal/thermal_zone2/temp", "48000\n")
+            .write("sys/class/hwmon/hwmon0/name", "cpu_thermal\n")
+            .write("sys/class/hwmon/hwmon0/temp1_input", "48000\n");
+
+        let sensors: Vec<(String, f32)> = ThermalSensor::read_all(root.path())
+            .into_iter()
+            .map(|s| (s.name, s.current))
+            .collect();
+        assert_eq!(
+            sensors,
+            vec![
+                ("cpu_thermal".to_string(), 48.0),
+                ("x86_pkg_temp".to_string(), 55.0),
+                ("x86_pkg_temp".to_string(), 61.0),
+            ]
+        );
+    }
+}